@@ -19,7 +19,7 @@ pub struct Cli {
 }
 
 impl Cli {
-    pub fn new() -> Params {
+    pub fn params() -> Params {
         let cli = Cli::parse();
         let home = dirs::home_dir().expect("Could not find home directory");
 
@@ -1 +1,2 @@
+#[allow(clippy::module_inception)]
 pub mod cli;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+use log::info;
+use crate::model::format::{DateFormat, Formats, SizeFormat};
+
+/// User settings, kept as `key = value` lines. Unknown keys and values that
+/// do not parse are skipped, leaving the default.
+#[derive(Default)]
+pub struct Config {
+    file: Option<PathBuf>,
+    pub formats: Formats,
+    pub left: PaneColumns,
+    pub right: PaneColumns,
+}
+
+/// Columns of one pane in ordinary directories and in the trash, as
+/// `id:width` pairs; checked by the panes.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct PaneColumns {
+    pub files: Option<String>,
+    pub trash: Option<String>,
+}
+
+impl Config {
+    /// Settings in `$XDG_CONFIG_HOME/caesar-commander/config`,
+    /// `~/.config/...` if unset.
+    pub fn open() -> Self {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| dirs::home_dir().map(|home| home.join(".config")));
+
+        match config_home {
+            Some(config_home) => Self::at(config_home.join("caesar-commander/config")),
+            None => Config::default(),
+        }
+    }
+
+    pub fn at(file: PathBuf) -> Self {
+        let mut config = Config::default();
+        match fs::read_to_string(&file) {
+            Ok(text) => config.parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => info!("Could not read the config: {:?}", e),
+        }
+        config.file = Some(file);
+        config
+    }
+
+    fn parse(&mut self, text: &str) {
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                info!("Skipping config line {:?}", line);
+                continue;
+            };
+
+            let (key, value) = (key.trim(), value.trim());
+            let applied = match key {
+                "size_format" => SizeFormat::from_name(value).map(|size| self.formats.size = size).is_some(),
+                "date_format" => DateFormat::from_name(value).map(|date| self.formats.date = date).is_some(),
+                _ => self.columns_entry(key).map(|entry| *entry = Some(value.to_string())).is_some(),
+            };
+            if !applied {
+                info!("Skipping config line {:?}", line);
+            }
+        }
+    }
+
+    /// Where a columns key goes.
+    fn columns_entry(&mut self, key: &str) -> Option<&mut Option<String>> {
+        Some(match key {
+            "left_columns" => &mut self.left.files,
+            "left_trash_columns" => &mut self.left.trash,
+            "right_columns" => &mut self.right.files,
+            "right_trash_columns" => &mut self.right.trash,
+            _ => return None,
+        })
+    }
+
+    pub fn save(&self) {
+        let Some(file) = &self.file else { return };
+
+        let mut text = format!(
+            "size_format = {}\ndate_format = {}\n",
+            self.formats.size.name(),
+            self.formats.date.name(),
+        );
+        let columns = [
+            ("left_columns", &self.left.files),
+            ("left_trash_columns", &self.left.trash),
+            ("right_columns", &self.right.files),
+            ("right_trash_columns", &self.right.trash),
+        ];
+        for (key, value) in columns {
+            if let Some(value) = value {
+                text.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        if let Err(e) = Self::write(file, &text) {
+            info!("Could not save the config: {:?}", e);
+        }
+    }
+
+    fn write(file: &Path, text: &str) -> io::Result<()> {
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = file.with_extension("tmp");
+        fs::write(&temp, text)?;
+        fs::rename(&temp, file)
+    }
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use tempfile::tempdir;
+    use crate::file_system::config::Config;
+    use crate::model::format::{DateFormat, Formats, SizeFormat};
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let dir = tempdir().unwrap();
+        let config = Config::at(dir.path().join("config"));
+
+        assert_eq!(config.formats, Formats::default());
+    }
+
+    #[test]
+    fn settings_survive_a_save() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("nested/config");
+
+        let mut config = Config::at(file.clone());
+        config.formats = Formats { size: SizeFormat::Thousands, date: DateFormat::Custom("%d.%m. %H:%M".into()) };
+        config.left.files = Some("icon:30 name:120".into());
+        config.right.trash = Some("name:200 deleted:150".into());
+        config.save();
+
+        let saved = Config::at(file);
+        assert_eq!(saved.formats, config.formats);
+        assert_eq!(saved.left, config.left);
+        assert_eq!(saved.right, config.right);
+    }
+
+    #[test]
+    fn bad_lines_are_skipped() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("config");
+        fs::write(&file, "# comment\nsize_format = si\ndate_format = %Q\nnonsense\ncolor = blue\n").unwrap();
+
+        let config = Config::at(file);
+        assert_eq!(config.formats, Formats { size: SizeFormat::Si, date: DateFormat::default() });
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How often `measure` reports its running total.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a directory holds, as far as it was measured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirSize {
+    pub bytes: u64,
+    /// Still being measured when false.
+    pub done: bool,
+}
+
+/// Adds up the sizes of everything below `root`, not following links.
+/// Hard-linked files count once and entries that cannot be read are skipped.
+/// File systems mounted below `root` are left out unless `cross_devices`.
+///
+/// `progress` gets the running total now and then; the walk stops, giving
+/// `None`, as soon as it returns false.
+pub fn measure(root: &Path, cross_devices: bool, mut progress: impl FnMut(u64) -> bool) -> Option<u64> {
+    let Ok(metadata) = fs::symlink_metadata(root) else { return Some(0) };
+    let device = metadata.dev();
+
+    let mut seen = HashSet::new();
+    let mut pending = vec![root.to_path_buf()];
+    let mut total = 0;
+    let mut last_report = Instant::now();
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            if last_report.elapsed() >= REPORT_INTERVAL {
+                if !progress(total) {
+                    return None;
+                }
+                last_report = Instant::now();
+            }
+
+            // does not follow links
+            let Ok(metadata) = entry.metadata() else { continue };
+            if metadata.is_dir() {
+                if cross_devices || metadata.dev() == device {
+                    pending.push(entry.path());
+                }
+            } else if metadata.nlink() < 2 || seen.insert((metadata.dev(), metadata.ino())) {
+                total += metadata.len();
+            }
+        }
+    }
+    Some(total)
+}
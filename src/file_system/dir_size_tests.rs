@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+    use crate::file_system::dir_size::measure;
+
+    #[test]
+    fn adds_up_nested_files() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("top"), [0; 10]).unwrap();
+        fs::write(dir.path().join("a/mid"), [0; 200]).unwrap();
+        fs::write(dir.path().join("a/b/deep"), [0; 3000]).unwrap();
+
+        assert_eq!(measure(dir.path(), false, |_| true), Some(3210));
+    }
+
+    #[test]
+    fn links_are_not_followed_and_hard_links_count_once() {
+        let dir = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        fs::write(outside.path().join("big"), [0; 5000]).unwrap();
+        fs::write(dir.path().join("file"), [0; 100]).unwrap();
+        fs::hard_link(dir.path().join("file"), dir.path().join("again")).unwrap();
+        symlink(outside.path(), dir.path().join("link")).unwrap();
+
+        let link_size = fs::symlink_metadata(dir.path().join("link")).unwrap().len();
+        assert_eq!(measure(dir.path(), false, |_| true), Some(100 + link_size));
+    }
+
+    #[test]
+    fn missing_directory_is_empty() {
+        let dir = tempdir().unwrap();
+
+        assert_eq!(measure(&dir.path().join("gone"), false, |_| true), Some(0));
+    }
+}
@@ -0,0 +1,35 @@
+use std::ffi::CString;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Space on the file system holding a path, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiskSpace {
+    /// What unprivileged users can still write.
+    pub free: u64,
+    pub total: u64,
+}
+
+impl DiskSpace {
+    pub fn of(path: &Path) -> io::Result<DiskSpace> {
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+        // SAFETY: `path` is NUL-terminated and `stat` is only read after the
+        // call filled it in.
+        let stat = unsafe {
+            if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            stat.assume_init()
+        };
+
+        let block = stat.f_frsize as u64;
+        Ok(DiskSpace {
+            free: stat.f_bavail as u64 * block,
+            total: stat.f_blocks as u64 * block,
+        })
+    }
+}
@@ -0,0 +1,20 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use tempfile::tempdir;
+    use crate::file_system::disk_space::DiskSpace;
+
+    #[test]
+    fn free_space_fits_in_total() {
+        let dir = tempdir().unwrap();
+        let space = DiskSpace::of(dir.path()).unwrap();
+
+        assert!(space.total > 0);
+        assert!(space.free <= space.total);
+    }
+
+    #[test]
+    fn missing_path_is_an_error() {
+        assert!(DiskSpace::of(Path::new("/nonexistent/caesar-commander")).is_err());
+    }
+}
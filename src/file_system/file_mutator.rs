@@ -1,19 +1,187 @@
-use std::path::Path;
+use std::ffi::{CString, OsStr, OsString};
+use std::io::{Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{symlink, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
 use std::{fs, io};
+use crate::model::conflict::{ConflictPolicy, LinkPolicy, TransferKind, TransferOutcome};
+
+const COPY_CHUNK: usize = 1 << 20;
+
+/// Hooks into long-running copies. An error returned from a hook aborts the
+/// copy with that error, which is how background jobs get cancelled.
+pub trait CopyObserver {
+    fn bytes_copied(&mut self, _bytes: u64) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn file_copied(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CopyObserver for () {}
 
 pub struct FileMutator;
 
 impl FileMutator {
+    /// Copies or moves `src` to `dst`, resolving an existing destination
+    /// according to `policy`.
+    pub fn transfer<T: AsRef<Path>, A: AsRef<Path>>(
+        src: T,
+        dst: A,
+        kind: TransferKind,
+        policy: ConflictPolicy,
+    ) -> io::Result<TransferOutcome> {
+        Self::transfer_observed(src.as_ref(), dst.as_ref(), kind, policy, LinkPolicy::Preserve, &mut ())
+    }
+
+    /// `transfer` reporting copy progress to `observer`, treating links as
+    /// `links` says. A dereferencing move only resolves `src` itself; the
+    /// contents of a moved directory are left as they are.
+    pub fn transfer_observed(
+        src: &Path,
+        dst: &Path,
+        kind: TransferKind,
+        policy: ConflictPolicy,
+        links: LinkPolicy,
+        observer: &mut dyn CopyObserver,
+    ) -> io::Result<TransferOutcome> {
+        let mut dst = dst.to_path_buf();
+        let mut replaced = None;
+
+        if let Ok(dst_meta) = fs::symlink_metadata(&dst) {
+            let src_meta = fs::symlink_metadata(src)?;
+
+            match policy {
+                ConflictPolicy::Ask => return Ok(TransferOutcome::Conflict),
+                ConflictPolicy::Skip => return Ok(TransferOutcome::Skipped),
+                ConflictPolicy::RenameWithSuffix => dst = Self::free_name(&dst),
+                ConflictPolicy::Overwrite | ConflictPolicy::OverwriteIfNewer => {
+                    if src_meta.dev() == dst_meta.dev() && src_meta.ino() == dst_meta.ino() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("{:?} cannot overwrite itself", src),
+                        ));
+                    }
+                    if src.starts_with(&dst) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("{:?} cannot overwrite {:?}, which holds it", src, dst),
+                        ));
+                    }
+
+                    let src_is_dir = match links {
+                        LinkPolicy::Preserve => src_meta.is_dir(),
+                        LinkPolicy::Dereference => fs::metadata(src)?.is_dir(),
+                    };
+                    if src_is_dir && dst_meta.is_dir() {
+                        Self::merge_dir(src, &dst, kind, policy, links, observer)?;
+                        return Ok(TransferOutcome::Transferred(dst));
+                    }
+
+                    if policy == ConflictPolicy::OverwriteIfNewer
+                        && src_meta.modified()? <= dst_meta.modified()?
+                    {
+                        return Ok(TransferOutcome::Skipped);
+                    }
+
+                    // the old destination is only deleted once the new one
+                    // is in place, and put back if the transfer fails
+                    let aside = Self::hidden_sibling(&dst, "replaced");
+                    fs::rename(&dst, &aside)?;
+                    replaced = Some(aside);
+                }
+            }
+        }
+
+        let transferred = Self::transfer_to_free(src, &dst, kind, links, observer);
+        match (transferred, replaced) {
+            (Ok(()), Some(aside)) => Self::durable_remove(&aside)?,
+            (Ok(()), None) => {}
+            (Err(e), Some(aside)) => {
+                if fs::symlink_metadata(&dst).is_ok() {
+                    let _ = Self::remove_entry(&dst);
+                }
+                fs::rename(&aside, &dst)?;
+                return Err(e);
+            }
+            (Err(e), None) => return Err(e),
+        }
+        Ok(TransferOutcome::Transferred(dst))
+    }
+
+    /// Transfers the entries of the directory `src` into the existing
+    /// directory `dst`, resolving each conflict by `policy`, so entries only
+    /// found in `dst` stay. A moved `src` is removed once nothing is left
+    /// in it.
+    fn merge_dir(
+        src: &Path,
+        dst: &Path,
+        kind: TransferKind,
+        policy: ConflictPolicy,
+        links: LinkPolicy,
+        observer: &mut dyn CopyObserver,
+    ) -> io::Result<()> {
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            Self::transfer_observed(&entry.path(), &dst.join(entry.file_name()), kind, policy, links, observer)?;
+        }
+
+        if kind == TransferKind::Move {
+            // entries skipped as not newer keep the directory
+            match fs::remove_dir(src) {
+                Err(e) if e.kind() == io::ErrorKind::DirectoryNotEmpty => {}
+                res => {
+                    res?;
+                    fs::File::open(src.parent().unwrap())?.sync_all()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The transfer itself, once nothing is at `dst`.
+    fn transfer_to_free(
+        src: &Path,
+        dst: &Path,
+        kind: TransferKind,
+        links: LinkPolicy,
+        observer: &mut dyn CopyObserver,
+    ) -> io::Result<()> {
+        let src_is_link = fs::symlink_metadata(src)?.is_symlink();
+        match kind {
+            TransferKind::Copy => Self::durable_copy_observed(src, dst, links, observer),
+            TransferKind::Move if src_is_link && links == LinkPolicy::Dereference => {
+                Self::durable_copy_observed(src, dst, links, observer)?;
+                Self::durable_remove(src)
+            }
+            TransferKind::Move => Self::durable_move_observed(src, dst, observer),
+        }
+    }
+
     pub fn durable_move<T: AsRef<Path>, A: AsRef<Path>>(src: T, dst: A) -> io::Result<()> {
-        // open & sync the source file
-        let src = src.as_ref();
-        fs::OpenOptions::new().read(true).open(src)?.sync_all()?;
+        Self::durable_move_observed(src.as_ref(), dst.as_ref(), &mut ())
+    }
+
+    /// `durable_move` reporting progress when it has to fall back to copying.
+    pub fn durable_move_observed(src: &Path, dst: &Path, observer: &mut dyn CopyObserver) -> io::Result<()> {
+        // sync the source first; opening anything else could block, as
+        // with a FIFO
+        let metadata = fs::symlink_metadata(src)?;
+        if metadata.is_file() || metadata.is_dir() {
+            fs::OpenOptions::new().read(true).open(src)?.sync_all()?;
+        }
 
         let src_dir = src.parent().unwrap();
-        let dst = dst.as_ref();
         let dst_dir = dst.parent().unwrap();
 
-        fs::rename(src, dst)?;
+        match fs::rename(src, dst) {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                return Self::move_by_copy(src, dst, observer);
+            }
+            res => res?,
+        }
 
         fs::File::open(src_dir)?.sync_all()?;
         if src_dir != dst_dir {
@@ -21,4 +189,301 @@ impl FileMutator {
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Renames `path` within its directory. `new_name` must be a plain name:
+    /// not empty, no `/`, not `.`/`..`, and not taken by another entry.
+    /// Returns the new path.
+    pub fn durable_rename<T: AsRef<Path>, A: AsRef<OsStr>>(path: T, new_name: A) -> io::Result<PathBuf> {
+        let path = path.as_ref();
+        let new_name = new_name.as_ref();
+
+        let mut components = Path::new(new_name).components();
+        let is_plain = matches!(components.next(), Some(Component::Normal(c)) if c == new_name)
+            && components.next().is_none();
+        if !is_plain {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid name {:?}", new_name),
+            ));
+        }
+
+        let dst = path.with_file_name(new_name);
+        if let Ok(existing) = fs::symlink_metadata(&dst) {
+            // a case-only rename on a case-insensitive filesystem finds itself
+            let src_meta = fs::symlink_metadata(path)?;
+            if existing.dev() != src_meta.dev() || existing.ino() != src_meta.ino() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{:?} already exists", new_name),
+                ));
+            }
+        }
+
+        Self::durable_move(path, &dst)?;
+        Ok(dst)
+    }
+
+    /// Fallback for moves between filesystems: copies, verifies the copy
+    /// against the source and only then removes the source. A partial copy
+    /// is cleaned up if anything fails before the source is touched.
+    pub(crate) fn move_by_copy(src: &Path, dst: &Path, observer: &mut dyn CopyObserver) -> io::Result<()> {
+        let dst_existed = fs::symlink_metadata(dst).is_ok();
+
+        let copied = Self::durable_copy_observed(src, dst, LinkPolicy::Preserve, observer)
+            .and_then(|_| Self::verify_copy(src, dst));
+        if let Err(e) = copied {
+            if !dst_existed {
+                let _ = Self::remove_entry(dst);
+            }
+            return Err(e);
+        }
+
+        Self::remove_entry(src)?;
+        fs::File::open(src.parent().unwrap())?.sync_all()?;
+        Ok(())
+    }
+
+    /// Copies a file or a whole directory tree, keeping permissions and
+    /// modification times, and reports progress to `observer`. Every copied
+    /// entry and the destination's parent directory are synced before
+    /// returning. With `LinkPolicy::Dereference` links are copied as what
+    /// they point at, and a link back into a directory being copied is an
+    /// error. If the copy fails or is aborted, whatever was created at a
+    /// previously free `dst` is removed.
+    pub fn durable_copy_observed(
+        src: &Path,
+        dst: &Path,
+        links: LinkPolicy,
+        observer: &mut dyn CopyObserver,
+    ) -> io::Result<()> {
+        if dst.starts_with(src) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot copy {:?} into itself", src),
+            ));
+        }
+
+        let dst_existed = fs::symlink_metadata(dst).is_ok();
+        if let Err(e) = Self::copy_entry(src, dst, links, &mut Vec::new(), observer) {
+            if !dst_existed {
+                let _ = Self::remove_entry(dst);
+            }
+            return Err(e);
+        }
+
+        let dst_dir = dst.parent().unwrap();
+        fs::File::open(dst_dir)?.sync_all()?;
+        Ok(())
+    }
+
+    /// `parents` holds the (dev, ino) of every directory being copied above
+    /// `src`, to catch link loops when dereferencing.
+    fn copy_entry(
+        src: &Path,
+        dst: &Path,
+        links: LinkPolicy,
+        parents: &mut Vec<(u64, u64)>,
+        observer: &mut dyn CopyObserver,
+    ) -> io::Result<()> {
+        let metadata = match links {
+            LinkPolicy::Preserve => fs::symlink_metadata(src)?,
+            LinkPolicy::Dereference => fs::metadata(src)?,
+        };
+        let file_type = metadata.file_type();
+
+        if file_type.is_symlink() {
+            symlink(fs::read_link(src)?, dst)?;
+        } else if file_type.is_dir() {
+            let id = (metadata.dev(), metadata.ino());
+            if parents.contains(&id) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{:?} links back into a directory being copied", src),
+                ));
+            }
+
+            match fs::create_dir(dst) {
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && dst.is_dir() => {}
+                res => res?,
+            }
+
+            parents.push(id);
+            for entry in fs::read_dir(src)? {
+                let entry = entry?;
+                Self::copy_entry(&entry.path(), &dst.join(entry.file_name()), links, parents, observer)?;
+            }
+            parents.pop();
+
+            // mtime has to be set after the children are written, and
+            // permissions last in case they make the directory unreadable
+            let dir = fs::File::open(dst)?;
+            dir.set_modified(metadata.modified()?)?;
+            fs::set_permissions(dst, metadata.permissions())?;
+            dir.sync_all()?;
+        } else if file_type.is_fifo() {
+            Self::make_fifo(dst, metadata.permissions().mode())?;
+        } else if !file_type.is_file() {
+            // reading a device could go on forever and a socket cannot be
+            // read at all
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{:?} is a socket or device and cannot be copied", src),
+            ));
+        } else {
+            // written next to `dst` and renamed over it once complete, so a
+            // failed or cancelled copy leaves an existing file as it was
+            let partial = Self::hidden_sibling(dst, "partial");
+            let copied = Self::copy_file(src, &partial, &metadata, observer)
+                .and_then(|_| fs::rename(&partial, dst));
+            if let Err(e) = copied {
+                let _ = fs::remove_file(&partial);
+                return Err(e);
+            }
+            observer.file_copied()?;
+        }
+        Ok(())
+    }
+
+    /// Creates a FIFO instead of copying one, which would block on opening it.
+    fn make_fifo(path: &Path, mode: u32) -> io::Result<()> {
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: `path` is a valid NUL-terminated string for the call.
+        if unsafe { libc::mkfifo(path.as_ptr(), (mode & 0o7777) as libc::mode_t) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Copies the contents of `src` to the new file `dst` and syncs it.
+    fn copy_file(src: &Path, dst: &Path, metadata: &fs::Metadata, observer: &mut dyn CopyObserver) -> io::Result<()> {
+        let mut reader = fs::File::open(src)?;
+        let mut writer = fs::OpenOptions::new().write(true).create_new(true).open(dst)?;
+
+        let mut buf = vec![0; COPY_CHUNK];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buf[..n])?;
+            observer.bytes_copied(n as u64)?;
+        }
+
+        writer.set_modified(metadata.modified()?)?;
+        fs::set_permissions(dst, metadata.permissions())?;
+        writer.sync_all()
+    }
+
+    fn verify_copy(src: &Path, dst: &Path) -> io::Result<()> {
+        let src_meta = fs::symlink_metadata(src)?;
+        let dst_meta = fs::symlink_metadata(dst)?;
+        let mismatch = |what: &str| {
+            Err(io::Error::other(format!("{} mismatch after copying {:?} to {:?}", what, src, dst)))
+        };
+
+        if src_meta.file_type() != dst_meta.file_type() {
+            return mismatch("type");
+        }
+
+        if src_meta.is_symlink() {
+            if fs::read_link(src)? != fs::read_link(dst)? {
+                return mismatch("link target");
+            }
+        } else if src_meta.is_dir() {
+            for entry in fs::read_dir(src)? {
+                let entry = entry?;
+                Self::verify_copy(&entry.path(), &dst.join(entry.file_name()))?;
+            }
+        } else if src_meta.len() != dst_meta.len() {
+            return mismatch("size");
+        }
+        Ok(())
+    }
+
+    /// Creates `relative` under `base`; nested paths like `a/b/c` create every
+    /// missing level. Each new directory's parent is synced. Returns the full
+    /// path of the deepest directory.
+    pub fn durable_create_dir<T: AsRef<Path>, A: AsRef<Path>>(base: T, relative: A) -> io::Result<PathBuf> {
+        let relative = relative.as_ref();
+        let is_plain = relative.components().next().is_some()
+            && relative.components().all(|c| matches!(c, Component::Normal(_)));
+        if !is_plain {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid directory name {:?}", relative),
+            ));
+        }
+
+        let mut path = base.as_ref().to_path_buf();
+        let mut created = false;
+        for component in relative.components() {
+            path.push(component);
+            match fs::create_dir(&path) {
+                Ok(()) => {
+                    created = true;
+                    fs::File::open(path.parent().unwrap())?.sync_all()?;
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && path.is_dir() => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !created {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{:?} already exists", path)));
+        }
+        Ok(path)
+    }
+
+    /// Deletes a file or a whole directory tree for good and syncs the
+    /// parent directory.
+    pub fn durable_remove<T: AsRef<Path>>(path: T) -> io::Result<()> {
+        let path = path.as_ref();
+        Self::remove_entry(path)?;
+        fs::File::open(path.parent().unwrap())?.sync_all()?;
+        Ok(())
+    }
+
+    fn remove_entry(path: &Path) -> io::Result<()> {
+        if fs::symlink_metadata(path)?.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    /// A free hidden name next to `path`, like `.name.tag-1`, for keeping
+    /// an entry out of the way.
+    fn hidden_sibling(path: &Path, tag: &str) -> PathBuf {
+        let name = path.file_name().unwrap_or_default();
+        (1..)
+            .map(|n| {
+                let mut hidden = OsString::from(".");
+                hidden.push(name);
+                hidden.push(format!(".{}-{}", tag, n));
+                path.with_file_name(hidden)
+            })
+            .find(|candidate| fs::symlink_metadata(candidate).is_err())
+            .unwrap()
+    }
+
+    /// First of `name (1).ext`, `name (2).ext`, ... that does not exist yet.
+    fn free_name(path: &Path) -> PathBuf {
+        (1..)
+            .map(|n| path.with_file_name(Self::numbered_name(path.file_name().unwrap_or_default(), n)))
+            .find(|candidate| fs::symlink_metadata(candidate).is_err())
+            .unwrap()
+    }
+
+    /// `name.ext` -> `name (n).ext`
+    pub(crate) fn numbered_name(file_name: &OsStr, n: usize) -> OsString {
+        let path = Path::new(file_name);
+        let mut name = path.file_stem().unwrap_or_default().to_os_string();
+        name.push(format!(" ({})", n));
+        if let Some(ext) = path.extension() {
+            name.push(".");
+            name.push(ext);
+        }
+        name
+    }
+}
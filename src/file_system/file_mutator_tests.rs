@@ -1,13 +1,17 @@
 #[cfg(test)]
 mod tests {
-    use std::{fs, io, io::Write, path::PathBuf};
+    use std::{fs, io, io::Write, path::{Path, PathBuf}};
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use std::time::{Duration, SystemTime};
     use tempfile::tempdir;
-    use crate::file_system::file_mutator::FileMutator;
+    use crate::file_system::file_mutator::{CopyObserver, FileMutator};
+    use crate::model::conflict::{ConflictPolicy, LinkPolicy, TransferKind, TransferOutcome};
 
     fn create_file(path: &PathBuf, contents: &str) -> io::Result<()> {
         let mut f = fs::OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(true)
             .open(path)?;
         f.write_all(contents.as_bytes())?;
         f.sync_all()
@@ -72,4 +76,582 @@ mod tests {
             assert_eq!(fs::read_to_string(&dst).unwrap(), "first");
         }
     }
-}
\ No newline at end of file
+
+    /// Copies to a free destination the way a copy job does.
+    fn copy(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<TransferOutcome> {
+        FileMutator::transfer_observed(
+            src.as_ref(), dst.as_ref(), TransferKind::Copy, ConflictPolicy::Ask, LinkPolicy::Preserve, &mut (),
+        )
+    }
+
+    #[test]
+    fn copy_file_keeps_source() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("orig.txt");
+        let dst = dir.path().join("copy.txt");
+
+        create_file(&src, "payload").unwrap();
+        copy(&src, &dst).unwrap();
+
+        assert_eq!(fs::read_to_string(&src).unwrap(), "payload");
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "payload");
+    }
+
+    #[test]
+    fn copy_preserves_permissions_and_mtime() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("script.sh");
+        let dst = dir.path().join("script-copy.sh");
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+
+        create_file(&src, "#!/bin/sh").unwrap();
+        fs::set_permissions(&src, fs::Permissions::from_mode(0o751)).unwrap();
+        fs::File::open(&src).unwrap().set_modified(mtime).unwrap();
+
+        copy(&src, &dst).unwrap();
+
+        let metadata = fs::metadata(&dst).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o751);
+        assert_eq!(metadata.modified().unwrap(), mtime);
+    }
+
+    #[test]
+    fn copy_directory_tree() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tree");
+        let dst = dir.path().join("tree-copy");
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+
+        fs::create_dir_all(src.join("nested/deeper")).unwrap();
+        create_file(&src.join("top.txt"), "top").unwrap();
+        create_file(&src.join("nested/deeper/leaf.txt"), "leaf").unwrap();
+        symlink("top.txt", src.join("link")).unwrap();
+        fs::File::open(src.join("nested")).unwrap().set_modified(mtime).unwrap();
+
+        copy(&src, &dst).unwrap();
+
+        assert!(src.join("nested/deeper/leaf.txt").exists());
+        assert_eq!(fs::read_to_string(dst.join("top.txt")).unwrap(), "top");
+        assert_eq!(fs::read_to_string(dst.join("nested/deeper/leaf.txt")).unwrap(), "leaf");
+        assert_eq!(fs::read_link(dst.join("link")).unwrap(), PathBuf::from("top.txt"));
+        assert_eq!(fs::metadata(dst.join("nested")).unwrap().modified().unwrap(), mtime);
+    }
+
+    #[test]
+    fn dereferencing_copy_copies_link_targets() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tree");
+        let dst = dir.path().join("tree-copy");
+        fs::create_dir_all(dir.path().join("outside")).unwrap();
+        create_file(&dir.path().join("outside/data.txt"), "data").unwrap();
+        fs::create_dir(&src).unwrap();
+        symlink("../outside/data.txt", src.join("file_link")).unwrap();
+        symlink("../outside", src.join("dir_link")).unwrap();
+
+        FileMutator::durable_copy_observed(&src, &dst, LinkPolicy::Dereference, &mut ()).unwrap();
+
+        assert!(!fs::symlink_metadata(dst.join("file_link")).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(dst.join("file_link")).unwrap(), "data");
+        assert!(fs::symlink_metadata(dst.join("dir_link")).unwrap().is_dir());
+        assert_eq!(fs::read_to_string(dst.join("dir_link/data.txt")).unwrap(), "data");
+    }
+
+    #[test]
+    fn dereferencing_copy_rejects_link_loops() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tree");
+        let dst = dir.path().join("tree-copy");
+        fs::create_dir(&src).unwrap();
+        symlink("..", src.join("parent")).unwrap();
+        symlink(".", src.join("itself")).unwrap();
+
+        let err = FileMutator::durable_copy_observed(&src, &dst, LinkPolicy::Dereference, &mut ()).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!dst.exists());
+    }
+
+    #[test]
+    fn dereferencing_move_replaces_link_with_target_copy() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("target.txt");
+        let link = dir.path().join("link");
+        let dst = dir.path().join("moved");
+        create_file(&target, "data").unwrap();
+        symlink("target.txt", &link).unwrap();
+
+        FileMutator::transfer_observed(&link, &dst, TransferKind::Move, ConflictPolicy::Ask, LinkPolicy::Dereference, &mut ())
+            .unwrap();
+
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert!(!fs::symlink_metadata(&dst).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "data");
+        assert!(target.exists());
+    }
+
+    #[test]
+    fn special_files_are_recreated_or_refused() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::FileTypeExt;
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+        let fifo = CString::new(src.join("pipe").as_os_str().as_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o640) }, 0);
+        create_file(&src.join("file"), "data").unwrap();
+
+        let dst = dir.path().join("dst");
+        copy(&src, &dst).unwrap();
+        let copied = fs::symlink_metadata(dst.join("pipe")).unwrap();
+        assert!(copied.file_type().is_fifo());
+        assert_eq!(copied.permissions().mode() & 0o777, 0o640);
+        assert_eq!(fs::read_to_string(dst.join("file")).unwrap(), "data");
+
+        let moved = dir.path().join("moved-pipe");
+        FileMutator::durable_move(src.join("pipe"), &moved).unwrap();
+        assert!(fs::symlink_metadata(&moved).unwrap().file_type().is_fifo());
+
+        let _socket = UnixListener::bind(src.join("socket")).unwrap();
+        let err = copy(&src, dir.path().join("again")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(!dir.path().join("again").exists());
+    }
+
+    #[test]
+    fn copy_into_itself_errors() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tree");
+        fs::create_dir(&src).unwrap();
+
+        let err = copy(&src, src.join("tree")).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!src.join("tree").exists());
+    }
+
+    #[test]
+    fn copy_nonexistent_source_errors() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("missing");
+        let dst = dir.path().join("dst");
+
+        let err = copy(&src, &dst).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn move_by_copy_file() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        let dst = dir.path().join("b.txt");
+
+        create_file(&src, "across devices").unwrap();
+        FileMutator::move_by_copy(&src, &dst, &mut ()).unwrap();
+
+        assert!(!src.exists());
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "across devices");
+    }
+
+    #[test]
+    fn move_by_copy_directory_tree() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tree");
+        let dst = dir.path().join("moved");
+
+        fs::create_dir_all(src.join("sub")).unwrap();
+        create_file(&src.join("sub/leaf.txt"), "leaf").unwrap();
+        symlink("sub/leaf.txt", src.join("link")).unwrap();
+
+        FileMutator::move_by_copy(&src, &dst, &mut ()).unwrap();
+
+        assert!(!src.exists());
+        assert_eq!(fs::read_to_string(dst.join("sub/leaf.txt")).unwrap(), "leaf");
+        assert_eq!(fs::read_link(dst.join("link")).unwrap(), PathBuf::from("sub/leaf.txt"));
+    }
+
+    #[test]
+    fn move_by_copy_failure_keeps_source_and_cleans_destination() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tree");
+        fs::create_dir(&src).unwrap();
+        create_file(&src.join("file.txt"), "data").unwrap();
+
+        let err = FileMutator::move_by_copy(&src, &src.join("inner"), &mut ()).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read_to_string(src.join("file.txt")).unwrap(), "data");
+        assert!(!src.join("inner").exists());
+    }
+
+    fn conflicting_pair(dir: &tempfile::TempDir) -> (PathBuf, PathBuf) {
+        let src = dir.path().join("src/report.txt");
+        let dst = dir.path().join("dst/report.txt");
+        fs::create_dir_all(src.parent().unwrap()).unwrap();
+        fs::create_dir_all(dst.parent().unwrap()).unwrap();
+        create_file(&src, "new").unwrap();
+        create_file(&dst, "old").unwrap();
+        (src, dst)
+    }
+
+    fn set_mtime(path: &PathBuf, secs: u64) {
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        fs::File::open(path).unwrap().set_modified(mtime).unwrap();
+    }
+
+    #[test]
+    fn transfer_without_conflict_ignores_policy() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        let dst = dir.path().join("b.txt");
+        create_file(&src, "a").unwrap();
+
+        let outcome = FileMutator::transfer(&src, &dst, TransferKind::Move, ConflictPolicy::Ask).unwrap();
+
+        assert_eq!(outcome, TransferOutcome::Transferred(dst.clone()));
+        assert!(!src.exists());
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "a");
+    }
+
+    #[test]
+    fn transfer_ask_reports_conflict_untouched() {
+        let dir = tempdir().unwrap();
+        let (src, dst) = conflicting_pair(&dir);
+
+        let outcome = FileMutator::transfer(&src, &dst, TransferKind::Move, ConflictPolicy::Ask).unwrap();
+
+        assert_eq!(outcome, TransferOutcome::Conflict);
+        assert_eq!(fs::read_to_string(&src).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "old");
+    }
+
+    #[test]
+    fn transfer_skip_leaves_both() {
+        let dir = tempdir().unwrap();
+        let (src, dst) = conflicting_pair(&dir);
+
+        let outcome = FileMutator::transfer(&src, &dst, TransferKind::Copy, ConflictPolicy::Skip).unwrap();
+
+        assert_eq!(outcome, TransferOutcome::Skipped);
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "old");
+    }
+
+    #[test]
+    fn transfer_overwrite_replaces_destination() {
+        let dir = tempdir().unwrap();
+        let (src, dst) = conflicting_pair(&dir);
+
+        let outcome = FileMutator::transfer(&src, &dst, TransferKind::Copy, ConflictPolicy::Overwrite).unwrap();
+
+        assert_eq!(outcome, TransferOutcome::Transferred(dst.clone()));
+        assert!(src.exists());
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "new");
+    }
+
+    #[test]
+    fn transfer_overwrite_replaces_directory_with_file() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src/entry");
+        let dst = dir.path().join("dst/entry");
+        fs::create_dir_all(src.parent().unwrap()).unwrap();
+        fs::create_dir_all(dst.join("inside")).unwrap();
+        create_file(&src, "file").unwrap();
+
+        FileMutator::transfer(&src, &dst, TransferKind::Move, ConflictPolicy::Overwrite).unwrap();
+
+        assert!(dst.is_file());
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "file");
+    }
+
+    #[test]
+    fn transfer_overwrite_onto_itself_errors() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("same.txt");
+        create_file(&src, "keep me").unwrap();
+
+        let err = FileMutator::transfer(&src, &src, TransferKind::Copy, ConflictPolicy::Overwrite).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read_to_string(&src).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn transfer_overwrite_of_an_ancestor_errors() {
+        let dir = tempdir().unwrap();
+        let outer = dir.path().join("x");
+        let src = outer.join("x");
+        fs::create_dir_all(&src).unwrap();
+        create_file(&src.join("data"), "keep me").unwrap();
+
+        for kind in [TransferKind::Copy, TransferKind::Move] {
+            let err = FileMutator::transfer(&src, &outer, kind, ConflictPolicy::Overwrite).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(fs::read_to_string(src.join("data")).unwrap(), "keep me");
+        }
+    }
+
+    /// Aborts a copy as soon as any bytes are written, like a cancelled job.
+    struct Cancel;
+
+    impl CopyObserver for Cancel {
+        fn bytes_copied(&mut self, _bytes: u64) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"))
+        }
+    }
+
+    #[test]
+    fn failed_overwrite_puts_the_old_destination_back() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src/entry");
+        let dst = dir.path().join("dst/entry");
+        fs::create_dir_all(src.parent().unwrap()).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        create_file(&src, "new").unwrap();
+        create_file(&dst.join("old"), "old").unwrap();
+
+        let result = FileMutator::transfer_observed(
+            &src, &dst, TransferKind::Copy, ConflictPolicy::Overwrite, LinkPolicy::Preserve, &mut Cancel,
+        );
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(dst.join("old")).unwrap(), "old");
+        assert_eq!(fs::read_dir(dst.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn overwriting_a_folder_with_a_folder_merges_them() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("a/photos");
+        let dst = dir.path().join("b/photos");
+        fs::create_dir_all(src.join("2024")).unwrap();
+        fs::create_dir_all(dst.join("2024")).unwrap();
+        create_file(&src.join("shared.jpg"), "new").unwrap();
+        create_file(&src.join("2024/deep.jpg"), "new deep").unwrap();
+        create_file(&dst.join("shared.jpg"), "old").unwrap();
+        create_file(&dst.join("only_in_dst.jpg"), "mine").unwrap();
+        create_file(&dst.join("2024/only_in_dst.jpg"), "mine too").unwrap();
+
+        let outcome = FileMutator::transfer(&src, &dst, TransferKind::Copy, ConflictPolicy::Overwrite).unwrap();
+
+        assert_eq!(outcome, TransferOutcome::Transferred(dst.clone()));
+        assert_eq!(fs::read_to_string(dst.join("shared.jpg")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dst.join("2024/deep.jpg")).unwrap(), "new deep");
+        assert_eq!(fs::read_to_string(dst.join("only_in_dst.jpg")).unwrap(), "mine");
+        assert_eq!(fs::read_to_string(dst.join("2024/only_in_dst.jpg")).unwrap(), "mine too");
+        assert_eq!(fs::read_dir(dst.parent().unwrap()).unwrap().count(), 1);
+        assert!(src.join("2024/deep.jpg").exists());
+    }
+
+    #[test]
+    fn merging_move_removes_the_source_unless_something_was_skipped() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("a/photos");
+        let dst = dir.path().join("b/photos");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        create_file(&src.join("older.jpg"), "src").unwrap();
+        create_file(&src.join("newer.jpg"), "src").unwrap();
+        create_file(&dst.join("older.jpg"), "dst").unwrap();
+        create_file(&dst.join("newer.jpg"), "dst").unwrap();
+        set_mtime(&src.join("older.jpg"), 1_000_000_000);
+        set_mtime(&dst.join("older.jpg"), 2_000_000_000);
+        set_mtime(&src.join("newer.jpg"), 2_000_000_000);
+        set_mtime(&dst.join("newer.jpg"), 1_000_000_000);
+
+        FileMutator::transfer(&src, &dst, TransferKind::Move, ConflictPolicy::OverwriteIfNewer).unwrap();
+
+        assert_eq!(fs::read_to_string(dst.join("newer.jpg")).unwrap(), "src");
+        assert_eq!(fs::read_to_string(dst.join("older.jpg")).unwrap(), "dst");
+        assert!(src.join("older.jpg").exists());
+        assert!(!src.join("newer.jpg").exists());
+
+        fs::remove_file(src.join("older.jpg")).unwrap();
+        create_file(&src.join("last.jpg"), "last").unwrap();
+        FileMutator::transfer(&src, &dst, TransferKind::Move, ConflictPolicy::Overwrite).unwrap();
+
+        assert!(!src.exists());
+        assert_eq!(fs::read_to_string(dst.join("last.jpg")).unwrap(), "last");
+    }
+
+    #[test]
+    fn cancelled_copy_over_a_file_keeps_it() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dst = dir.path().join("dst.txt");
+        create_file(&src, "new contents").unwrap();
+        create_file(&dst, "old contents").unwrap();
+
+        let err = FileMutator::durable_copy_observed(&src, &dst, LinkPolicy::Preserve, &mut Cancel).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "old contents");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn transfer_rename_with_suffix_picks_free_name() {
+        let dir = tempdir().unwrap();
+        let (src, dst) = conflicting_pair(&dir);
+        let taken = dir.path().join("dst/report (1).txt");
+        create_file(&taken, "taken").unwrap();
+
+        let outcome = FileMutator::transfer(&src, &dst, TransferKind::Move, ConflictPolicy::RenameWithSuffix).unwrap();
+
+        let expected = dir.path().join("dst/report (2).txt");
+        assert_eq!(outcome, TransferOutcome::Transferred(expected.clone()));
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&taken).unwrap(), "taken");
+        assert_eq!(fs::read_to_string(&expected).unwrap(), "new");
+    }
+
+    #[test]
+    fn transfer_overwrite_if_newer_replaces_older_destination() {
+        let dir = tempdir().unwrap();
+        let (src, dst) = conflicting_pair(&dir);
+        set_mtime(&src, 2_000_000_000);
+        set_mtime(&dst, 1_000_000_000);
+
+        let outcome = FileMutator::transfer(&src, &dst, TransferKind::Move, ConflictPolicy::OverwriteIfNewer).unwrap();
+
+        assert_eq!(outcome, TransferOutcome::Transferred(dst.clone()));
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "new");
+    }
+
+    #[test]
+    fn transfer_overwrite_if_newer_skips_newer_destination() {
+        let dir = tempdir().unwrap();
+        let (src, dst) = conflicting_pair(&dir);
+        set_mtime(&src, 1_000_000_000);
+        set_mtime(&dst, 2_000_000_000);
+
+        let outcome = FileMutator::transfer(&src, &dst, TransferKind::Move, ConflictPolicy::OverwriteIfNewer).unwrap();
+
+        assert_eq!(outcome, TransferOutcome::Skipped);
+        assert!(src.exists());
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "old");
+    }
+
+    #[test]
+    fn durable_create_dir_single() {
+        let dir = tempdir().unwrap();
+
+        let created = FileMutator::durable_create_dir(dir.path(), "photos").unwrap();
+
+        assert_eq!(created, dir.path().join("photos"));
+        assert!(created.is_dir());
+    }
+
+    #[test]
+    fn durable_create_dir_nested_reuses_existing_levels() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("a")).unwrap();
+
+        let created = FileMutator::durable_create_dir(dir.path(), "a/b/c").unwrap();
+
+        assert_eq!(created, dir.path().join("a/b/c"));
+        assert!(created.is_dir());
+    }
+
+    #[test]
+    fn durable_create_dir_existing_errors() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+
+        let err = FileMutator::durable_create_dir(dir.path(), "a/b").unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn durable_create_dir_over_file_errors() {
+        let dir = tempdir().unwrap();
+        create_file(&dir.path().join("taken"), "").unwrap();
+
+        let err = FileMutator::durable_create_dir(dir.path(), "taken/sub").unwrap_err();
+
+        assert_ne!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(dir.path().join("taken").is_file());
+    }
+
+    #[test]
+    fn durable_create_dir_rejects_escaping_names() {
+        let dir = tempdir().unwrap();
+
+        for name in ["", "/abs", "../up", "a/../b", "./here"] {
+            let err = FileMutator::durable_create_dir(dir.path(), name).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", name);
+        }
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn durable_rename_in_place() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("draft.txt");
+        create_file(&src, "text").unwrap();
+
+        let renamed = FileMutator::durable_rename(&src, "final.txt").unwrap();
+
+        assert_eq!(renamed, dir.path().join("final.txt"));
+        assert!(!src.exists());
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), "text");
+    }
+
+    #[test]
+    fn durable_rename_rejects_invalid_names() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("file");
+        create_file(&src, "").unwrap();
+
+        for name in ["", "a/b", "/abs", ".", "..", "dir/"] {
+            let err = FileMutator::durable_rename(&src, name).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", name);
+        }
+        assert!(src.exists());
+    }
+
+    #[test]
+    fn durable_rename_onto_existing_entry_errors() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        let other = dir.path().join("b.txt");
+        create_file(&src, "a").unwrap();
+        create_file(&other, "b").unwrap();
+
+        let err = FileMutator::durable_rename(&src, "b.txt").unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&other).unwrap(), "b");
+        assert!(src.exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn non_utf8_names_survive_move_rename_and_copy() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        let src_dir = dir.path().join("src");
+        let dst_dir = dir.path().join("dst");
+        fs::create_dir(&src_dir).unwrap();
+        fs::create_dir(&dst_dir).unwrap();
+
+        let name = OsStr::from_bytes(b"na\xefve");
+        let other = OsStr::from_bytes(b"\xff\xfe");
+        let src = src_dir.join(name);
+        create_file(&src, "bytes").unwrap();
+
+        FileMutator::durable_move(&src, dst_dir.join(name)).unwrap();
+        assert!(!src.exists());
+
+        let renamed = FileMutator::durable_rename(dst_dir.join(name), other).unwrap();
+        assert_eq!(renamed.file_name(), Some(other));
+
+        copy(&dst_dir, src_dir.join(name)).unwrap();
+        assert_eq!(fs::read_to_string(src_dir.join(name).join(other)).unwrap(), "bytes");
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::Write;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io, process};
+use log::info;
+use crate::file_system::file_mutator::FileMutator;
+use crate::file_system::trash::Trash;
+use crate::model::conflict::ConflictPolicy;
+
+const MAX_ENTRIES: usize = 500;
+
+/// A completed file operation, in terms of where things were and where they
+/// ended up.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JournalOp {
+    /// Moves and renames, as (from, to) pairs.
+    Move(Vec<(PathBuf, PathBuf)>),
+    /// Newly created directories, outermost first.
+    CreateDirs(Vec<PathBuf>),
+    /// Trashed items, as (original, trashed) pairs.
+    Trash(Vec<(PathBuf, PathBuf)>),
+}
+
+impl JournalOp {
+    pub fn describe(&self) -> String {
+        let name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        match self {
+            JournalOp::Move(pairs) if pairs.len() == 1 => format!("move of {}", name(&pairs[0].0)),
+            JournalOp::Move(pairs) => format!("move of {} items", pairs.len()),
+            JournalOp::CreateDirs(paths) => format!("creation of {}", paths.first().map(|p| name(p)).unwrap_or_default()),
+            JournalOp::Trash(pairs) if pairs.len() == 1 => format!("trashing of {}", name(&pairs[0].0)),
+            JournalOp::Trash(pairs) => format!("trashing of {} items", pairs.len()),
+        }
+    }
+
+    /// Where the affected entries live right now.
+    fn current(&self) -> Vec<&Path> {
+        match self {
+            JournalOp::Move(pairs) | JournalOp::Trash(pairs) => pairs.iter().map(|(_, to)| to.as_path()).collect(),
+            JournalOp::CreateDirs(_) => Vec::new(),
+        }
+    }
+}
+
+/// Identity of an entry, to tell whether it was touched since it was
+/// recorded. Directory mtimes change with their contents, so they are left out.
+#[derive(Clone, Debug, PartialEq)]
+struct Stamp {
+    dev: u64,
+    ino: u64,
+    len: u64,
+    mtime: Option<i64>,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> io::Result<Stamp> {
+        let m = fs::symlink_metadata(path)?;
+        Ok(Stamp {
+            dev: m.dev(),
+            ino: m.ino(),
+            len: if m.is_dir() { 0 } else { m.len() },
+            mtime: (!m.is_dir()).then(|| m.mtime() * 1_000_000_000 + m.mtime_nsec()),
+        })
+    }
+
+    fn encode(&self) -> String {
+        let mtime = self.mtime.map(|t| t.to_string()).unwrap_or_else(|| "-".into());
+        format!("{}:{}:{}:{}", self.dev, self.ino, self.len, mtime)
+    }
+
+    fn decode(value: &str) -> Option<Stamp> {
+        let mut parts = value.split(':');
+        let stamp = Stamp {
+            dev: parts.next()?.parse().ok()?,
+            ino: parts.next()?.parse().ok()?,
+            len: parts.next()?.parse().ok()?,
+            mtime: match parts.next()? {
+                "-" => None,
+                t => Some(t.parse().ok()?),
+            },
+        };
+        parts.next().is_none().then_some(stamp)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    op: JournalOp,
+    /// One per entry of `op.current()`.
+    stamps: Vec<Stamp>,
+}
+
+impl Entry {
+    fn new(op: JournalOp) -> io::Result<Entry> {
+        let stamps = op.current().into_iter().map(Stamp::of).collect::<io::Result<_>>()?;
+        Ok(Entry { op, stamps })
+    }
+}
+
+/// Persistent undo/redo history of file operations. Undoing replays the
+/// inverse operation, but only if the affected entries are unchanged since
+/// and their old locations are free. An entry that cannot be undone or
+/// redone is dropped from the history along with the error.
+pub struct Journal {
+    file: Option<PathBuf>,
+    trash: Trash,
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+}
+
+impl Journal {
+    /// History kept in `$XDG_STATE_HOME/caesar-commander/journal`,
+    /// `~/.local/state/...` if unset.
+    pub fn open(trash: Trash) -> Self {
+        let state_home = env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| dirs::home_dir().map(|home| home.join(".local/state")));
+
+        match state_home {
+            Some(state_home) => Self::at(state_home.join("caesar-commander/journal"), trash),
+            None => Journal { file: None, trash, undo: Vec::new(), redo: Vec::new() },
+        }
+    }
+
+    pub fn at(file: PathBuf, trash: Trash) -> Self {
+        let mut journal = Journal { file: Some(file), trash, undo: Vec::new(), redo: Vec::new() };
+        if let Err(e) = journal.load() {
+            info!("Could not read the journal: {:?}", e);
+        }
+        journal
+    }
+
+    /// Adds a finished operation. Anything that could be redone is forgotten.
+    pub fn record(&mut self, op: JournalOp) {
+        let empty = match &op {
+            JournalOp::Move(pairs) | JournalOp::Trash(pairs) => pairs.is_empty(),
+            JournalOp::CreateDirs(paths) => paths.is_empty(),
+        };
+        if empty {
+            return;
+        }
+
+        match Entry::new(op) {
+            Ok(entry) => {
+                self.undo.push(entry);
+                if self.undo.len() > MAX_ENTRIES {
+                    self.undo.remove(0);
+                }
+                self.redo.clear();
+                self.save();
+            }
+            Err(e) => info!("Not journaling an operation whose result is gone: {:?}", e),
+        }
+    }
+
+    /// Reverts the latest operation. Returns what was undone, if anything.
+    pub fn undo(&mut self) -> io::Result<Option<String>> {
+        let Some(entry) = self.undo.pop() else { return Ok(None) };
+        let description = entry.op.describe();
+
+        let result = self.revert(&entry);
+        match &result {
+            Ok(redo) => self.redo.push(redo.clone()),
+            Err(e) => info!("Cannot undo {}: {:?}", description, e),
+        }
+        self.save();
+        result
+            .map(|_| Some(description.clone()))
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot undo {}: {}", description, e)))
+    }
+
+    /// Performs the latest undone operation again.
+    pub fn redo(&mut self) -> io::Result<Option<String>> {
+        let Some(entry) = self.redo.pop() else { return Ok(None) };
+        let description = entry.op.describe();
+
+        let result = self.replay(&entry);
+        match &result {
+            Ok(undo) => self.undo.push(undo.clone()),
+            Err(e) => info!("Cannot redo {}: {:?}", description, e),
+        }
+        self.save();
+        result
+            .map(|_| Some(description.clone()))
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot redo {}: {}", description, e)))
+    }
+
+    /// Undoes `entry`, returning the entry that redoes it. Redo entries keep
+    /// the stamps of the operation's sources rather than its results.
+    fn revert(&self, entry: &Entry) -> io::Result<Entry> {
+        Self::check_unchanged(entry)?;
+
+        match &entry.op {
+            JournalOp::Move(pairs) => {
+                let back: Vec<(PathBuf, PathBuf)> = pairs.iter().map(|(from, to)| (to.clone(), from.clone())).collect();
+                Self::check_free(pairs.iter().map(|(from, _)| from.as_path()), &entry.op.current())?;
+                Self::move_all(&back)?;
+
+                let stamps = pairs.iter().map(|(from, _)| Stamp::of(from)).collect::<io::Result<_>>()?;
+                Ok(Entry { op: entry.op.clone(), stamps })
+            }
+            JournalOp::CreateDirs(paths) => {
+                for (i, path) in paths.iter().enumerate().rev() {
+                    let expected = paths.get(i + 1).map(|child| child.file_name().unwrap_or_default());
+                    let mut read_dir = fs::read_dir(path)?;
+                    if read_dir.any(|child| child.map(|c| Some(c.file_name().as_os_str()) != expected).unwrap_or(true)) {
+                        return Err(io::Error::new(io::ErrorKind::DirectoryNotEmpty, format!("{:?} is not empty", path)));
+                    }
+                }
+                for path in paths.iter().rev() {
+                    FileMutator::durable_remove(path)?;
+                }
+                Ok(Entry { op: entry.op.clone(), stamps: Vec::new() })
+            }
+            JournalOp::Trash(pairs) => {
+                Self::check_free(pairs.iter().map(|(original, _)| original.as_path()), &[])?;
+                let mut stamps = Vec::with_capacity(pairs.len());
+                for (original, trashed) in pairs {
+                    self.trash.restore(trashed, original, ConflictPolicy::Skip)?;
+                    stamps.push(Stamp::of(original)?);
+                }
+                Ok(Entry { op: entry.op.clone(), stamps })
+            }
+        }
+    }
+
+    /// Performs `entry.op` again from the state its undo left behind,
+    /// returning the entry that undoes it.
+    fn replay(&self, entry: &Entry) -> io::Result<Entry> {
+        match &entry.op {
+            JournalOp::Move(pairs) => {
+                let origins: Vec<&Path> = pairs.iter().map(|(from, _)| from.as_path()).collect();
+                Self::check_stamps(&origins, &entry.stamps)?;
+                Self::check_free(pairs.iter().map(|(_, to)| to.as_path()), &origins)?;
+                Self::move_all(pairs)?;
+                Entry::new(entry.op.clone())
+            }
+            JournalOp::CreateDirs(paths) => {
+                Self::check_free(paths.iter().map(PathBuf::as_path), &[])?;
+                for path in paths {
+                    fs::create_dir(path)?;
+                    fs::File::open(path.parent().unwrap())?.sync_all()?;
+                }
+                Entry::new(entry.op.clone())
+            }
+            JournalOp::Trash(pairs) => {
+                let originals: Vec<&Path> = pairs.iter().map(|(original, _)| original.as_path()).collect();
+                Self::check_stamps(&originals, &entry.stamps)?;
+                let pairs = originals.into_iter()
+                    .map(|original| Ok((original.to_path_buf(), self.trash.trash(original)?)))
+                    .collect::<io::Result<Vec<_>>>()?;
+                Entry::new(JournalOp::Trash(pairs))
+            }
+        }
+    }
+
+    fn check_unchanged(entry: &Entry) -> io::Result<()> {
+        Self::check_stamps(&entry.op.current(), &entry.stamps)
+    }
+
+    fn check_stamps(paths: &[&Path], stamps: &[Stamp]) -> io::Result<()> {
+        for (path, stamp) in paths.iter().zip(stamps) {
+            match Stamp::of(path) {
+                Ok(current) if current == *stamp => {}
+                Ok(_) => return Err(io::Error::other(format!("{:?} was changed since", path))),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    return Err(io::Error::new(io::ErrorKind::NotFound, format!("{:?} no longer exists", path)));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Every path must be free, except those being vacated by the same step.
+    fn check_free<'a>(paths: impl Iterator<Item = &'a Path>, vacated: &[&Path]) -> io::Result<()> {
+        let vacated: HashSet<&Path> = vacated.iter().copied().collect();
+        for path in paths {
+            if !vacated.contains(path) && fs::symlink_metadata(path).is_ok() {
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{:?} is taken", path)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves every (from, to) pair via a temporary name next to `from`, so
+    /// swaps work. Completed moves are undone if one fails.
+    fn move_all(pairs: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+        let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let result = Self::move_steps(pairs, &mut done);
+
+        if result.is_err() {
+            for (from, to) in done.iter().rev() {
+                let _ = FileMutator::durable_move(to, from);
+            }
+        }
+        result
+    }
+
+    fn move_steps(pairs: &[(PathBuf, PathBuf)], done: &mut Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+        if let Some(missing) = pairs.iter().filter_map(|(_, to)| to.parent()).find(|parent| !parent.is_dir()) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{:?} no longer exists", missing)));
+        }
+
+        let mut staged = Vec::with_capacity(pairs.len());
+        for (i, (from, _)) in pairs.iter().enumerate() {
+            let temp = from.with_file_name(format!(".caesar-undo-{}-{}", process::id(), i));
+            FileMutator::durable_move(from, &temp)?;
+            done.push((from.clone(), temp.clone()));
+            staged.push(temp);
+        }
+
+        for ((_, to), temp) in pairs.iter().zip(staged) {
+            FileMutator::durable_move(&temp, to)?;
+            done.push((temp, to.clone()));
+        }
+        Ok(())
+    }
+
+    /// One line per entry: stack, kind, stamps and percent-encoded paths,
+    /// separated by tabs.
+    fn save(&self) {
+        let Some(file) = &self.file else { return };
+
+        let mut text = String::new();
+        for (stack, entries) in [("undo", &self.undo), ("redo", &self.redo)] {
+            for entry in entries {
+                let (kind, paths): (&str, Vec<&Path>) = match &entry.op {
+                    JournalOp::Move(pairs) => ("move", pairs.iter().flat_map(|(a, b)| [a.as_path(), b.as_path()]).collect()),
+                    JournalOp::CreateDirs(paths) => ("mkdir", paths.iter().map(PathBuf::as_path).collect()),
+                    JournalOp::Trash(pairs) => ("trash", pairs.iter().flat_map(|(a, b)| [a.as_path(), b.as_path()]).collect()),
+                };
+                let stamps: Vec<String> = entry.stamps.iter().map(Stamp::encode).collect();
+
+                text.push_str(&format!("{}\t{}\t{}", stack, kind, stamps.join(",")));
+                for path in paths {
+                    text.push('\t');
+                    text.push_str(&Trash::encode_path(path));
+                }
+                text.push('\n');
+            }
+        }
+
+        if let Err(e) = Self::write_durably(file, text.as_bytes()) {
+            info!("Could not save the journal: {:?}", e);
+        }
+    }
+
+    fn write_durably(file: &Path, data: &[u8]) -> io::Result<()> {
+        let dir = file.parent().unwrap();
+        fs::create_dir_all(dir)?;
+
+        let temp = file.with_extension("tmp");
+        let mut f = fs::File::create(&temp)?;
+        f.write_all(data)?;
+        f.sync_all()?;
+        fs::rename(&temp, file)?;
+        fs::File::open(dir)?.sync_all()
+    }
+
+    fn load(&mut self) -> io::Result<()> {
+        let Some(file) = &self.file else { return Ok(()) };
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for line in text.lines() {
+            match Self::parse_line(line) {
+                Some(("undo", entry)) => self.undo.push(entry),
+                Some(("redo", entry)) => self.redo.push(entry),
+                _ => info!("Skipping bad journal line {:?}", line),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_line(line: &str) -> Option<(&str, Entry)> {
+        let mut fields = line.split('\t');
+        let stack = fields.next()?;
+        let kind = fields.next()?;
+        let stamps = match fields.next()? {
+            "" => Vec::new(),
+            stamps => stamps.split(',').map(Stamp::decode).collect::<Option<Vec<_>>>()?,
+        };
+        let paths: Vec<PathBuf> = fields
+            .map(|field| PathBuf::from(OsString::from_vec(Trash::decode_path(field))))
+            .collect();
+
+        let pairs = || {
+            paths.len().is_multiple_of(2).then(|| paths.chunks(2).map(|c| (c[0].clone(), c[1].clone())).collect::<Vec<_>>())
+        };
+        let op = match kind {
+            "move" => JournalOp::Move(pairs()?),
+            "trash" => JournalOp::Trash(pairs()?),
+            "mkdir" => JournalOp::CreateDirs(paths.clone()),
+            _ => return None,
+        };
+        (stamps.len() == op.current().len()).then_some((stack, Entry { op, stamps }))
+    }
+}
@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use tempfile::{tempdir, TempDir};
+    use crate::file_system::file_mutator::FileMutator;
+    use crate::file_system::journal::{Journal, JournalOp};
+    use crate::file_system::trash::Trash;
+
+    fn journal(dir: &TempDir) -> Journal {
+        Journal::at(dir.path().join("state/journal"), Trash::with_root(dir.path().join("data/Trash")))
+    }
+
+    fn moved(dir: &Path, from: &str, to: &str) -> JournalOp {
+        let (from, to) = (dir.join(from), dir.join(to));
+        FileMutator::durable_move(&from, &to).unwrap();
+        JournalOp::Move(vec![(from, to)])
+    }
+
+    #[test]
+    fn undo_and_redo_a_move() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), "data").unwrap();
+        let mut journal = journal(&dir);
+        journal.record(moved(dir.path(), "a", "b"));
+
+        assert_eq!(journal.undo().unwrap(), Some("move of a".to_string()));
+        assert!(dir.path().join("a").exists());
+        assert!(!dir.path().join("b").exists());
+
+        journal.redo().unwrap();
+        assert!(!dir.path().join("a").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("b")).unwrap(), "data");
+
+        journal.undo().unwrap();
+        assert!(dir.path().join("a").exists());
+    }
+
+    #[test]
+    fn nothing_to_undo() {
+        let dir = tempdir().unwrap();
+        let mut journal = journal(&dir);
+
+        assert_eq!(journal.undo().unwrap(), None);
+        assert_eq!(journal.redo().unwrap(), None);
+    }
+
+    #[test]
+    fn undo_refuses_when_entry_changed() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), "data").unwrap();
+        let mut journal = journal(&dir);
+        journal.record(moved(dir.path(), "a", "b"));
+
+        fs::write(dir.path().join("b"), "changed contents").unwrap();
+
+        let err = journal.undo().unwrap_err();
+        assert!(err.to_string().contains("changed since"), "{}", err);
+        assert!(dir.path().join("b").exists());
+        assert!(!dir.path().join("a").exists());
+        // the entry is dropped
+        assert_eq!(journal.undo().unwrap(), None);
+    }
+
+    #[test]
+    fn undo_refuses_when_old_location_taken() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), "data").unwrap();
+        let mut journal = journal(&dir);
+        journal.record(moved(dir.path(), "a", "b"));
+
+        fs::write(dir.path().join("a"), "new").unwrap();
+
+        assert_eq!(journal.undo().unwrap_err().kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(dir.path().join("a")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dir.path().join("b")).unwrap(), "data");
+    }
+
+    #[test]
+    fn undo_swap_of_names() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), "A").unwrap();
+        fs::write(dir.path().join("b"), "B").unwrap();
+        FileMutator::durable_move(dir.path().join("a"), dir.path().join("tmp")).unwrap();
+        FileMutator::durable_move(dir.path().join("b"), dir.path().join("a")).unwrap();
+        FileMutator::durable_move(dir.path().join("tmp"), dir.path().join("b")).unwrap();
+
+        let mut journal = journal(&dir);
+        journal.record(JournalOp::Move(vec![
+            (dir.path().join("a"), dir.path().join("b")),
+            (dir.path().join("b"), dir.path().join("a")),
+        ]));
+
+        journal.undo().unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("a")).unwrap(), "A");
+        assert_eq!(fs::read_to_string(dir.path().join("b")).unwrap(), "B");
+    }
+
+    #[test]
+    fn undo_mkdir_removes_only_created_and_empty_dirs() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("a")).unwrap();
+        FileMutator::durable_create_dir(dir.path(), "a/b/c").unwrap();
+
+        let mut journal = journal(&dir);
+        journal.record(JournalOp::CreateDirs(vec![dir.path().join("a/b"), dir.path().join("a/b/c")]));
+
+        journal.undo().unwrap();
+        assert!(dir.path().join("a").is_dir());
+        assert!(!dir.path().join("a/b").exists());
+
+        journal.redo().unwrap();
+        assert!(dir.path().join("a/b/c").is_dir());
+
+        fs::write(dir.path().join("a/b/c/file"), "").unwrap();
+        assert!(journal.undo().is_err());
+        assert!(dir.path().join("a/b/c/file").exists());
+    }
+
+    #[test]
+    fn undo_trash_restores_item() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("data/Trash"));
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "remember").unwrap();
+        let trashed = trash.trash(&file).unwrap();
+
+        let mut journal = journal(&dir);
+        journal.record(JournalOp::Trash(vec![(file.clone(), trashed.clone())]));
+
+        journal.undo().unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "remember");
+        assert!(!trashed.exists());
+        assert!(trash.list().unwrap().is_empty());
+
+        journal.redo().unwrap();
+        assert!(!file.exists());
+        assert_eq!(trash.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn history_survives_reopening() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a b"), "data").unwrap();
+        journal(&dir).record(moved(dir.path(), "a b", "c"));
+
+        let mut reopened = journal(&dir);
+        reopened.undo().unwrap();
+        assert!(dir.path().join("a b").exists());
+
+        let mut reopened = journal(&dir);
+        reopened.redo().unwrap();
+        assert!(dir.path().join("c").exists());
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), "").unwrap();
+        fs::write(dir.path().join("x"), "").unwrap();
+        let mut journal = journal(&dir);
+
+        journal.record(moved(dir.path(), "a", "b"));
+        journal.undo().unwrap();
+        journal.record(moved(dir.path(), "x", "y"));
+
+        assert_eq!(journal.redo().unwrap(), None);
+    }
+}
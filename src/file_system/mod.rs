@@ -1,4 +1,24 @@
 pub mod navigator;
 pub mod file_mutator;
 pub mod watcher;
+pub mod trash;
+pub mod multi_rename;
+pub mod operations;
+pub mod journal;
+pub mod config;
+pub mod users;
+pub mod dir_size;
+pub mod disk_space;
+pub mod path_input;
+mod navigator_tests;
 mod file_mutator_tests;
+mod trash_tests;
+mod multi_rename_tests;
+mod operations_tests;
+mod journal_tests;
+mod watcher_tests;
+mod config_tests;
+mod users_tests;
+mod dir_size_tests;
+mod disk_space_tests;
+mod path_input_tests;
@@ -0,0 +1,255 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::{fs, io, process};
+use regex::Regex;
+use crate::file_system::file_mutator::FileMutator;
+use crate::model::{file_name, Item};
+
+/// Longest file name most file systems allow, in bytes; no counter needs
+/// to be wider.
+const NAME_MAX: i64 = 255;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CaseTransform {
+    Unchanged,
+    Lower,
+    Upper,
+    /// First letter of every word upper case, the rest lower case.
+    Title,
+}
+
+/// What a batch rename should do. Templates understand `[N]` (name without
+/// extension), `[E]` (extension), `[C:start:step:width]` (counter, every part
+/// optional) and `[YMD]` (modification date).
+pub struct RenameSpec {
+    pub name_template: String,
+    pub ext_template: String,
+    pub search: String,
+    pub replace: String,
+    pub use_regex: bool,
+    pub case: CaseTransform,
+}
+
+impl Default for RenameSpec {
+    fn default() -> Self {
+        RenameSpec {
+            name_template: "[N]".into(),
+            ext_template: "[E]".into(),
+            search: String::new(),
+            replace: String::new(),
+            use_regex: false,
+            case: CaseTransform::Unchanged,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PlannedRename {
+    pub from: PathBuf,
+    pub old_name: String,
+    pub new_name: String,
+    /// Why this entry cannot be renamed, e.g. a name collision.
+    pub problem: Option<String>,
+}
+
+impl PlannedRename {
+    fn changes(&self) -> bool {
+        self.old_name != self.new_name
+    }
+
+    /// The path after renaming, with escaped bytes in `new_name` restored.
+    pub fn target(&self) -> PathBuf {
+        self.from.with_file_name(file_name::unescape(&self.new_name))
+    }
+}
+
+pub struct MultiRename;
+
+impl MultiRename {
+    /// Computes the new name of every item. `existing` are the names already
+    /// in the directory, used to detect collisions with entries outside the
+    /// batch. Errors are template or regex mistakes that affect every item.
+    pub fn plan(spec: &RenameSpec, items: &[Item], existing: &[String]) -> Result<Vec<PlannedRename>, String> {
+        let regex = match (spec.use_regex, spec.search.is_empty()) {
+            (true, false) => Some(Regex::new(&spec.search).map_err(|e| e.to_string())?),
+            _ => None,
+        };
+
+        let mut plan = items.iter().enumerate()
+            .map(|(index, item)| {
+                let path = Path::new(&item.name);
+                let stem = path.file_stem().map(file_name::escape).unwrap_or_default();
+                let ext = path.extension().map(file_name::escape).unwrap_or_default();
+
+                let name = Self::expand(&spec.name_template, item, &stem, &ext, index)?;
+                let ext = Self::expand(&spec.ext_template, item, &stem, &ext, index)?;
+                let mut new_name = if ext.is_empty() { name } else { format!("{}.{}", name, ext) };
+
+                if let Some(regex) = &regex {
+                    new_name = regex.replace_all(&new_name, spec.replace.as_str()).into_owned();
+                } else if !spec.search.is_empty() {
+                    new_name = new_name.replace(&spec.search, &spec.replace);
+                }
+
+                Ok(PlannedRename {
+                    from: item.path.clone(),
+                    old_name: item.display_name(),
+                    new_name: Self::transform_case(&new_name, spec.case),
+                    problem: None,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Self::detect_problems(&mut plan, existing);
+        Ok(plan)
+    }
+
+    /// Applies a plan without problems. Every entry is first moved to a
+    /// temporary name so swaps and chains (`a -> b`, `b -> c`) work; if any
+    /// step fails, the completed steps are undone and the error returned.
+    pub fn apply(plan: &[PlannedRename]) -> io::Result<()> {
+        if let Some(problem) = plan.iter().find_map(|p| p.problem.as_ref()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, problem.clone()));
+        }
+
+        // every single move, so a failure can be unwound in reverse order
+        let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let result = Self::apply_steps(plan, &mut done);
+
+        if result.is_err() {
+            for (from, to) in done.iter().rev() {
+                let _ = FileMutator::durable_move(to, from);
+            }
+        }
+        result
+    }
+
+    fn apply_steps(plan: &[PlannedRename], done: &mut Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+        let changing: Vec<&PlannedRename> = plan.iter().filter(|p| p.changes()).collect();
+
+        let mut staged = Vec::with_capacity(changing.len());
+        for (i, entry) in changing.iter().enumerate() {
+            let temp = entry.from.with_file_name(format!(".caesar-rename-{}-{}", process::id(), i));
+            FileMutator::durable_move(&entry.from, &temp)?;
+            done.push((entry.from.clone(), temp.clone()));
+            staged.push(temp);
+        }
+
+        for (entry, temp) in changing.iter().zip(staged) {
+            let target = entry.target();
+            if fs::symlink_metadata(&target).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{:?} already exists", entry.new_name),
+                ));
+            }
+            FileMutator::durable_move(&temp, &target)?;
+            done.push((temp, target));
+        }
+        Ok(())
+    }
+
+    fn expand(template: &str, item: &Item, stem: &str, ext: &str, index: usize) -> Result<String, String> {
+        let mut out = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('[') {
+            out.push_str(&rest[..start]);
+            let end = rest[start..].find(']')
+                .map(|i| start + i)
+                .ok_or_else(|| format!("missing ] in {:?}", template))?;
+
+            let token = &rest[start + 1..end];
+            match token {
+                "N" => out.push_str(stem),
+                "E" => out.push_str(ext),
+                "YMD" => {
+                    let modified = item.modified.ok_or_else(|| format!("unknown date of {:?}", item.display_name()))?;
+                    out.push_str(&modified.format("%Y%m%d").to_string());
+                }
+                _ if token == "C" || token.starts_with("C:") => out.push_str(&Self::counter(token, index)?),
+                _ => return Err(format!("unknown placeholder [{}]", token)),
+            }
+            rest = &rest[end + 1..];
+        }
+
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// `C:start:step:width`, defaults `C:1:1:1`.
+    fn counter(token: &str, index: usize) -> Result<String, String> {
+        let mut params = [1i64, 1, 1];
+        for (slot, part) in params.iter_mut().zip(token.split(':').skip(1)) {
+            if !part.is_empty() {
+                *slot = part.parse().map_err(|_| format!("bad counter [{}]", token))?;
+            }
+        }
+
+        let [start, step, width] = params;
+        if width > NAME_MAX {
+            return Err(format!("counter width in [{}] is over {}", token, NAME_MAX));
+        }
+        let value = i64::try_from(index).ok()
+            .and_then(|index| step.checked_mul(index))
+            .and_then(|offset| start.checked_add(offset))
+            .ok_or_else(|| format!("counter [{}] overflows", token))?;
+        Ok(format!("{:0width$}", value, width = width.max(0) as usize))
+    }
+
+    fn transform_case(name: &str, case: CaseTransform) -> String {
+        match case {
+            CaseTransform::Unchanged => name.to_string(),
+            CaseTransform::Lower => name.to_lowercase(),
+            CaseTransform::Upper => name.to_uppercase(),
+            CaseTransform::Title => {
+                let mut out = String::with_capacity(name.len());
+                let mut word_start = true;
+                for c in name.chars() {
+                    if word_start {
+                        out.extend(c.to_uppercase());
+                    } else {
+                        out.extend(c.to_lowercase());
+                    }
+                    word_start = !c.is_alphanumeric();
+                }
+                out
+            }
+        }
+    }
+
+    fn detect_problems(plan: &mut [PlannedRename], existing: &[String]) {
+        let leaving: HashSet<&str> = plan.iter()
+            .filter(|p| p.changes())
+            .map(|p| p.old_name.as_str())
+            .collect();
+        let occupied: HashSet<&str> = existing.iter()
+            .map(String::as_str)
+            .filter(|name| !leaving.contains(name))
+            .collect();
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for entry in plan.iter() {
+            *counts.entry(entry.new_name.as_str()).or_default() += 1;
+        }
+
+        let problems: Vec<Option<String>> = plan.iter()
+            .map(|entry| {
+                let name = entry.new_name.as_str();
+                if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+                    Some("invalid name".to_string())
+                } else if counts[name] > 1 {
+                    Some("duplicate name in batch".to_string())
+                } else if entry.changes() && occupied.contains(name) {
+                    Some("already exists".to_string())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        for (entry, problem) in plan.iter_mut().zip(problems) {
+            entry.problem = problem;
+        }
+    }
+}
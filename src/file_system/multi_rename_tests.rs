@@ -0,0 +1,236 @@
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+    use std::{fs, path::Path};
+    use chrono::{Local, TimeZone};
+    use tempfile::tempdir;
+    use crate::file_system::multi_rename::{CaseTransform, MultiRename, PlannedRename, RenameSpec};
+    use crate::model::{Item, ItemType};
+
+    fn item(dir: &Path, name: impl AsRef<OsStr>) -> Item {
+        Item {
+            name: name.as_ref().to_os_string(),
+            path: dir.join(name.as_ref()),
+            item_type: ItemType::File,
+            size: Some(0),
+            modified: Some(Local.with_ymd_and_hms(2024, 3, 9, 12, 0, 0).unwrap()),
+            trash_info: None,
+            unix: None,
+        }
+    }
+
+    fn new_names(plan: &[PlannedRename]) -> Vec<&str> {
+        plan.iter().map(|p| p.new_name.as_str()).collect()
+    }
+
+    fn spec(name_template: &str) -> RenameSpec {
+        RenameSpec { name_template: name_template.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn default_spec_keeps_names() {
+        let items = [item(Path::new("/d"), "a.txt"), item(Path::new("/d"), "noext")];
+
+        let plan = MultiRename::plan(&RenameSpec::default(), &items, &[]).unwrap();
+
+        assert_eq!(new_names(&plan), ["a.txt", "noext"]);
+        assert!(plan.iter().all(|p| p.problem.is_none()));
+    }
+
+    #[test]
+    fn templates_expand_name_counter_and_date() {
+        let items = [item(Path::new("/d"), "IMG.jpg"), item(Path::new("/d"), "DSC.JPG")];
+        let spec = RenameSpec {
+            name_template: "[YMD]_[N]_[C:10:5:3]".into(),
+            ext_template: "[E]".into(),
+            ..Default::default()
+        };
+
+        let plan = MultiRename::plan(&spec, &items, &[]).unwrap();
+
+        assert_eq!(new_names(&plan), ["20240309_IMG_010.jpg", "20240309_DSC_015.JPG"]);
+    }
+
+    #[test]
+    fn counter_defaults_and_empty_extension_template() {
+        let items = [item(Path::new("/d"), "x.log"), item(Path::new("/d"), "y.log")];
+        let spec = RenameSpec { name_template: "log[C]".into(), ext_template: String::new(), ..Default::default() };
+
+        let plan = MultiRename::plan(&spec, &items, &[]).unwrap();
+
+        assert_eq!(new_names(&plan), ["log1", "log2"]);
+    }
+
+    #[test]
+    fn template_errors_are_reported() {
+        let items = [item(Path::new("/d"), "a")];
+
+        assert!(MultiRename::plan(&spec("[X]"), &items, &[]).is_err());
+        assert!(MultiRename::plan(&spec("[N"), &items, &[]).is_err());
+        assert!(MultiRename::plan(&spec("[C:a]"), &items, &[]).is_err());
+    }
+
+    #[test]
+    fn counters_that_cannot_be_written_are_errors() {
+        let items = [item(Path::new("/d"), "a"), item(Path::new("/d"), "b")];
+
+        assert!(MultiRename::plan(&spec("[C:9223372036854775807]"), &items, &[]).is_err());
+        assert!(MultiRename::plan(&spec("[C:0:-9223372036854775807:1]"), &items, &[]).is_ok());
+        assert!(MultiRename::plan(&spec("[C:1:1:999999999999]"), &items, &[]).is_err());
+        assert!(MultiRename::plan(&spec("[C:1:1:255]"), &items, &[]).is_ok());
+    }
+
+    #[test]
+    fn plain_search_replace() {
+        let items = [item(Path::new("/d"), "a-b-c.txt")];
+        let spec = RenameSpec { search: "-".into(), replace: "_".into(), ..Default::default() };
+
+        let plan = MultiRename::plan(&spec, &items, &[]).unwrap();
+
+        assert_eq!(new_names(&plan), ["a_b_c.txt"]);
+    }
+
+    #[test]
+    fn regex_search_replace_with_groups() {
+        let items = [item(Path::new("/d"), "report-2023-11.pdf")];
+        let spec = RenameSpec {
+            search: r"(\d{4})-(\d{2})".into(),
+            replace: "${2}_$1".into(),
+            use_regex: true,
+            ..Default::default()
+        };
+
+        let plan = MultiRename::plan(&spec, &items, &[]).unwrap();
+
+        assert_eq!(new_names(&plan), ["report-11_2023.pdf"]);
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        let items = [item(Path::new("/d"), "a")];
+        let spec = RenameSpec { search: "(".into(), use_regex: true, ..Default::default() };
+
+        assert!(MultiRename::plan(&spec, &items, &[]).is_err());
+    }
+
+    #[test]
+    fn case_transforms() {
+        let items = [item(Path::new("/d"), "hello wORLD-file.TXT")];
+        let names = [
+            (CaseTransform::Lower, "hello world-file.txt"),
+            (CaseTransform::Upper, "HELLO WORLD-FILE.TXT"),
+            (CaseTransform::Title, "Hello World-File.Txt"),
+        ];
+
+        for (case, expected) in names {
+            let spec = RenameSpec { case, ..Default::default() };
+            let plan = MultiRename::plan(&spec, &items, &[]).unwrap();
+            assert_eq!(new_names(&plan), [expected]);
+        }
+    }
+
+    #[test]
+    fn collisions_are_flagged() {
+        let dir = Path::new("/d");
+        let items = [item(dir, "a.txt"), item(dir, "b.txt")];
+        let existing = ["a.txt", "b.txt", "same.txt"].map(String::from);
+
+        let plan = MultiRename::plan(&spec("same"), &items, &existing).unwrap();
+        assert!(plan.iter().all(|p| p.problem.as_deref() == Some("duplicate name in batch")));
+
+        let plan = MultiRename::plan(&spec("same[C]"), &items, &["same1.txt".to_string()]).unwrap();
+        assert_eq!(plan[0].problem.as_deref(), Some("already exists"));
+        assert!(plan[1].problem.is_none());
+
+        let spec = RenameSpec { search: ".txt".into(), replace: "/x".into(), ..Default::default() };
+        let plan = MultiRename::plan(&spec, &items, &existing).unwrap();
+        assert_eq!(plan[0].problem.as_deref(), Some("invalid name"));
+    }
+
+    #[test]
+    fn names_freed_by_the_batch_are_not_collisions() {
+        let dir = Path::new("/d");
+        let items = [item(dir, "1"), item(dir, "2")];
+        let existing = ["1", "2"].map(String::from);
+        let spec = RenameSpec { name_template: "[C:2:-1]".into(), ..Default::default() };
+
+        let plan = MultiRename::plan(&spec, &items, &existing).unwrap();
+
+        assert_eq!(new_names(&plan), ["2", "1"]);
+        assert!(plan.iter().all(|p| p.problem.is_none()));
+    }
+
+    #[test]
+    fn apply_swaps_names() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("1"), "one").unwrap();
+        fs::write(dir.path().join("2"), "two").unwrap();
+        let items = [item(dir.path(), "1"), item(dir.path(), "2")];
+        let existing = ["1", "2"].map(String::from);
+        let spec = RenameSpec { name_template: "[C:2:-1]".into(), ..Default::default() };
+
+        let plan = MultiRename::plan(&spec, &items, &existing).unwrap();
+        MultiRename::apply(&plan).unwrap();
+
+        assert_eq!(fs::read_to_string(dir.path().join("1")).unwrap(), "two");
+        assert_eq!(fs::read_to_string(dir.path().join("2")).unwrap(), "one");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn apply_rolls_back_on_failure() {
+        let dir = tempdir().unwrap();
+        for name in ["a", "b", "c"] {
+            fs::write(dir.path().join(name), name).unwrap();
+        }
+        let items = [item(dir.path(), "a"), item(dir.path(), "b"), item(dir.path(), "c")];
+        let existing = ["a", "b", "c"].map(String::from);
+        let spec = RenameSpec { name_template: "[N]-[C]".into(), ..Default::default() };
+        let plan = MultiRename::plan(&spec, &items, &existing).unwrap();
+
+        // appears between preview and apply
+        fs::write(dir.path().join("c-3"), "intruder").unwrap();
+
+        assert!(MultiRename::apply(&plan).is_err());
+
+        for name in ["a", "b", "c"] {
+            assert_eq!(fs::read_to_string(dir.path().join(name)).unwrap(), name);
+        }
+        assert_eq!(fs::read_to_string(dir.path().join("c-3")).unwrap(), "intruder");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn apply_refuses_plans_with_problems() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), "").unwrap();
+        fs::write(dir.path().join("b"), "").unwrap();
+        let items = [item(dir.path(), "a"), item(dir.path(), "b")];
+
+        let plan = MultiRename::plan(&spec("same"), &items, &[]).unwrap();
+
+        assert!(MultiRename::apply(&plan).is_err());
+        assert!(dir.path().join("a").exists() && dir.path().join("b").exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn non_utf8_names_keep_their_bytes() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        let latin1 = OsStr::from_bytes(b"caf\xe9.txt");
+        fs::write(dir.path().join(latin1), "").unwrap();
+        let items = [item(dir.path(), latin1)];
+
+        let spec = RenameSpec { name_template: "[N]-[C]".into(), case: CaseTransform::Upper, ..Default::default() };
+        let plan = MultiRename::plan(&spec, &items, &[]).unwrap();
+        assert_eq!(plan[0].old_name, "caf\\xE9.txt");
+        assert_eq!(plan[0].new_name, "CAF\\XE9-1.TXT");
+
+        MultiRename::apply(&plan).unwrap();
+
+        assert!(dir.path().join(OsStr::from_bytes(b"CAF\xe9-1.TXT")).exists());
+        assert!(!dir.path().join(latin1).exists());
+    }
+}
@@ -1,53 +1,242 @@
-use std::fs;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
+use std::{fmt, fs, io};
 use chrono::{DateTime, Local};
+use crate::file_system::trash::Trash;
 use crate::model::*;
+use crate::model::file_name;
+
+/// One step of the path bar.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breadcrumb {
+    pub name: String,
+    pub path: PathBuf,
+}
 
 #[derive(Clone)]
 pub struct Navigator {
     pub current_path: PathBuf,
+    trash: Option<TrashLocation>,
+}
+
+/// The virtual "Trash" location. `current_path` points at the trash's
+/// `files/` directory while it is open.
+#[derive(Clone)]
+struct TrashLocation {
+    trash: Trash,
+    return_path: PathBuf,
+}
+
+/// Why a directory could not be listed.
+#[derive(Debug)]
+pub enum ListError {
+    PermissionDenied(PathBuf),
+    NotFound(PathBuf),
+    NotADirectory(PathBuf),
+    Io(PathBuf, io::Error),
+}
+
+impl ListError {
+    fn new(path: &Path, e: io::Error) -> Self {
+        let path = path.to_path_buf();
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => ListError::PermissionDenied(path),
+            io::ErrorKind::NotFound => ListError::NotFound(path),
+            io::ErrorKind::NotADirectory => ListError::NotADirectory(path),
+            _ => ListError::Io(path, e),
+        }
+    }
+}
+
+impl Display for ListError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ListError::PermissionDenied(path) => write!(f, "Permission denied: {}", path.display()),
+            ListError::NotFound(path) => write!(f, "No such directory: {}", path.display()),
+            ListError::NotADirectory(path) => write!(f, "Not a directory: {}", path.display()),
+            ListError::Io(path, e) => write!(f, "Cannot list {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for ListError {}
+
+/// An opened location, yielding its entries as they are read.
+pub struct Listing(Box<dyn Iterator<Item = Item> + Send>);
+
+impl Iterator for Listing {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Item> {
+        self.0.next()
+    }
 }
 
 impl Navigator {
-    pub fn new(initial_path: &PathBuf) -> Self {
-        Navigator { current_path: initial_path.clone() }
-    }
-
-    pub fn open_dir(&mut self, path: &PathBuf) {
-        self.current_path = path.clone();
-    }
-
-    pub fn list_contents(&self) -> Vec<Item> {
-        match fs::read_dir(&self.current_path) {
-            Ok(read_dir) => {
-                read_dir.map(|entry| {
-                    let entry = entry.unwrap();
-                    let path = entry.path();
-                    let name = entry.file_name().into_string().unwrap();
-                    let metadata = fs::metadata(&path).unwrap();
-                    let size = metadata.len();
-                    let modified = metadata.modified().unwrap();
-                    let modified_dt = Self::system_time_to_date_time(modified);
-                    let item_type = if metadata.is_dir() {
-                        ItemType::Directory
-                    } else {
-                        ItemType::File
-                    };
-                    Item {
-                        name,
-                        path,
-                        item_type,
-                        size,
-                        modified: modified_dt,
-                    }
-                }).collect()
+    pub fn new(initial_path: &Path) -> Self {
+        Navigator { current_path: initial_path.to_path_buf(), trash: None }
+    }
+
+    pub fn open_dir(&mut self, path: &Path) {
+        self.trash = None;
+        self.current_path = path.to_path_buf();
+    }
+
+    pub fn open_trash(&mut self, trash: &Trash) -> Result<(), ListError> {
+        trash.ensure_dirs().map_err(|e| ListError::new(&trash.files_dir(), e))?;
+
+        let return_path = match self.trash.take() {
+            Some(location) => location.return_path,
+            None => self.current_path.clone(),
+        };
+        self.trash = Some(TrashLocation { trash: trash.clone(), return_path });
+        self.current_path = trash.files_dir();
+        Ok(())
+    }
+
+    pub fn in_trash(&self) -> bool {
+        self.trash.is_some()
+    }
+
+    /// Lists the current location at once.
+    #[cfg(test)]
+    pub fn list_contents(&self) -> Result<Vec<Item>, ListError> {
+        Ok(self.open_listing()?.collect())
+    }
+
+    /// Opens the current location for listing. Entries of a directory are
+    /// read, and their metadata fetched, only as the listing is iterated.
+    /// Only failing to open the directory itself is an error; an entry whose
+    /// metadata cannot be read is still listed, with unknown size and date.
+    pub fn open_listing(&self) -> Result<Listing, ListError> {
+        if let Some(location) = &self.trash {
+            let items = Self::list_trash(&location.trash)?;
+            return Ok(Listing(Box::new(items.into_iter())));
+        }
+
+        let read_dir = fs::read_dir(&self.current_path)
+            .map_err(|e| ListError::new(&self.current_path, e))?;
+
+        let items = read_dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| Self::item(entry.path(), entry.file_name()));
+        Ok(Listing(Box::new(items)))
+    }
+
+    /// Lists a single entry again, e.g. after the watcher reported a change
+    /// to it. `None` if it no longer exists.
+    pub fn entry(path: &Path) -> Option<Item> {
+        let name = path.file_name()?.to_os_string();
+        fs::symlink_metadata(path).ok()?;
+        Some(Self::item(path.to_path_buf(), name))
+    }
+
+    fn item(path: PathBuf, name: OsString) -> Item {
+        let own = fs::symlink_metadata(&path).ok();
+        let item_type = Self::item_type(&path, own.as_ref());
+        // links show what they point at
+        let metadata = fs::metadata(&path).ok();
+
+        Item {
+            name,
+            path,
+            item_type,
+            size: metadata.as_ref().map(fs::Metadata::len),
+            modified: metadata.and_then(|m| m.modified().ok()).map(Self::system_time_to_date_time),
+            trash_info: None,
+            unix: own.as_ref().map(Self::unix_meta),
+        }
+    }
+
+    fn unix_meta(metadata: &fs::Metadata) -> UnixMeta {
+        UnixMeta {
+            mode: metadata.mode() & 0o7777,
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            inode: metadata.ino(),
+            links: metadata.nlink(),
+            accessed: metadata.accessed().ok().map(Self::system_time_to_date_time),
+            created: metadata.created().ok().map(Self::system_time_to_date_time),
+        }
+    }
+
+    fn list_trash(trash: &Trash) -> Result<Vec<Item>, ListError> {
+        let entries = trash.list().map_err(|e| ListError::new(&trash.files_dir(), e))?;
+
+        let items = entries.into_iter()
+            .map(|entry| {
+                let metadata = fs::symlink_metadata(&entry.path).ok();
+                let name = entry.info.original_path.file_name()
+                    .unwrap_or(entry.path.as_os_str())
+                    .to_os_string();
+                let item_type = Self::item_type(&entry.path, metadata.as_ref());
+                Item {
+                    name,
+                    path: entry.path,
+                    item_type,
+                    size: metadata.as_ref().map(fs::Metadata::len),
+                    modified: metadata.as_ref().and_then(|m| m.modified().ok()).map(Self::system_time_to_date_time),
+                    trash_info: Some(entry.info),
+                    unix: metadata.as_ref().map(Self::unix_meta),
+                }
+            })
+            .collect();
+        Ok(items)
+    }
+
+    /// Type of the entry itself from its own `metadata`, not following
+    /// links. Unreadable entries count as files.
+    fn item_type(path: &Path, metadata: Option<&fs::Metadata>) -> ItemType {
+        let Some(metadata) = metadata else { return ItemType::File };
+        let file_type = metadata.file_type();
+
+        if file_type.is_symlink() {
+            let resolved = fs::metadata(path);
+            ItemType::Symlink {
+                target: fs::read_link(path).unwrap_or_default(),
+                broken: resolved.is_err(),
+                to_dir: resolved.is_ok_and(|metadata| metadata.is_dir()),
             }
-            Err(e) => { panic!("Readdir panic, #{}", e.to_string()) }
+        } else if file_type.is_dir() {
+            ItemType::Directory
+        } else if file_type.is_socket() {
+            ItemType::Socket
+        } else if file_type.is_fifo() {
+            ItemType::Fifo
+        } else if file_type.is_block_device() {
+            ItemType::BlockDevice
+        } else if file_type.is_char_device() {
+            ItemType::CharDevice
+        } else {
+            ItemType::File
         }
     }
 
+    /// Where a link points, made absolute against the link's directory.
+    pub fn link_target(link: &Path) -> io::Result<PathBuf> {
+        let target = fs::read_link(link)?;
+        Ok(link.parent().map(|dir| dir.join(&target)).unwrap_or(target))
+    }
+
+    /// The ".." row for the current location; the trash leads back to where
+    /// it was opened from. `None` at the root.
+    pub fn parent_item(&self) -> Option<Item> {
+        let parent = match &self.trash {
+            Some(location) => location.return_path.clone(),
+            None => self.current_path.parent()?.to_path_buf(),
+        };
+        Some(Item::parent_link(parent))
+    }
+
     pub fn go_up(&mut self) {
+        if let Some(location) = self.trash.take() {
+            self.current_path = location.return_path;
+            return;
+        }
+
         let parent = self.current_path.parent();
 
         match parent {
@@ -60,10 +249,20 @@ impl Navigator {
         }
     }
 
-    pub fn breadcrumbs(&self) -> Vec<String> {
-        self.current_path.iter().skip(1)
-            .map(|os_str| os_str.to_str().unwrap().to_string())
-            .collect()
+    /// The current path, from the root down; just "Trash" in the trash.
+    pub fn breadcrumbs(&self) -> Vec<Breadcrumb> {
+        if self.in_trash() {
+            return vec![Breadcrumb { name: "Trash".to_string(), path: self.current_path.clone() }];
+        }
+
+        let mut crumbs: Vec<Breadcrumb> = self.current_path.ancestors()
+            .map(|path| Breadcrumb {
+                name: path.file_name().map_or_else(|| path.display().to_string(), file_name::escape),
+                path: path.to_path_buf(),
+            })
+            .collect();
+        crumbs.reverse();
+        crumbs
     }
 
     fn system_time_to_date_time(system_time: SystemTime) -> DateTime<Local> {
@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use tempfile::tempdir;
+    use crate::file_system::navigator::{ListError, Navigator};
+    use crate::model::ItemType;
+
+    #[test]
+    fn list_contents_reports_missing_directory() {
+        let dir = tempdir().unwrap();
+        let navigator = Navigator::new(&dir.path().join("gone"));
+
+        assert!(matches!(navigator.list_contents(), Err(ListError::NotFound(p)) if p == dir.path().join("gone")));
+    }
+
+    #[test]
+    fn list_contents_reports_file_as_not_a_directory() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("file"), "").unwrap();
+        let navigator = Navigator::new(&dir.path().join("file"));
+
+        assert!(matches!(navigator.list_contents(), Err(ListError::NotADirectory(_))));
+    }
+
+    #[test]
+    fn list_contents_reports_unreadable_directory() {
+        // root can read anything
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+
+        let dir = tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+        let listed = Navigator::new(&locked).list_contents();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let err = listed.unwrap_err();
+        assert!(matches!(err, ListError::PermissionDenied(_)));
+        assert!(err.to_string().starts_with("Permission denied"));
+    }
+
+    #[test]
+    fn broken_symlink_is_listed_without_metadata() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("file"), "12345").unwrap();
+        symlink(dir.path().join("nowhere"), dir.path().join("dangling")).unwrap();
+
+        let mut items = Navigator::new(dir.path()).list_contents().unwrap();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "dangling");
+        assert_eq!(items[0].item_type, ItemType::Symlink { target: dir.path().join("nowhere"), broken: true, to_dir: false });
+        assert_eq!(items[0].size, None);
+        assert_eq!(items[0].modified, None);
+        assert_eq!(items[1].size, Some(5));
+        assert!(items[1].modified.is_some());
+    }
+
+    #[test]
+    fn entries_are_typed_without_following_links() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("dir")).unwrap();
+        fs::write(dir.path().join("file"), "123").unwrap();
+        symlink("dir", dir.path().join("to_dir")).unwrap();
+        symlink("file", dir.path().join("to_file")).unwrap();
+        let fifo = std::ffi::CString::new(dir.path().join("fifo").into_os_string().into_encoded_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+
+        let mut items = Navigator::new(dir.path()).list_contents().unwrap();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        let types: Vec<_> = items.iter().map(|item| item.item_type.clone()).collect();
+
+        assert_eq!(types, [
+            ItemType::Directory,
+            ItemType::Fifo,
+            ItemType::File,
+            ItemType::Symlink { target: "dir".into(), broken: false, to_dir: true },
+            ItemType::Symlink { target: "file".into(), broken: false, to_dir: false },
+        ]);
+        assert!(items[3].opens_as_directory());
+        assert!(!items[4].opens_as_directory());
+        // links show their target's size
+        assert_eq!(items[4].size, Some(3));
+    }
+
+    #[test]
+    fn link_target_resolves_relative_targets() {
+        let dir = tempdir().unwrap();
+        symlink("../elsewhere", dir.path().join("link")).unwrap();
+        symlink("/abs/path", dir.path().join("abs")).unwrap();
+
+        assert_eq!(Navigator::link_target(&dir.path().join("link")).unwrap(), dir.path().join("../elsewhere"));
+        assert_eq!(Navigator::link_target(&dir.path().join("abs")).unwrap(), Path::new("/abs/path"));
+        assert!(Navigator::link_target(dir.path()).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn non_utf8_names_are_listed_raw_and_shown_escaped() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        let name = OsStr::from_bytes(b"r\xe9sum\xe9");
+        let sub = dir.path().join(name);
+        fs::create_dir(&sub).unwrap();
+
+        let items = Navigator::new(dir.path()).list_contents().unwrap();
+        assert_eq!(items[0].name, name);
+        assert_eq!(items[0].path, sub);
+        assert_eq!(items[0].display_name(), "r\\xE9sum\\xE9");
+
+        let crumbs = Navigator::new(&sub).breadcrumbs();
+        assert_eq!(crumbs.last().unwrap().name, "r\\xE9sum\\xE9");
+        assert_eq!(crumbs.last().unwrap().path, sub);
+        assert_eq!(crumbs[0].name, "/");
+    }
+
+    #[test]
+    fn parent_item_leads_up_except_at_root() {
+        let dir = tempdir().unwrap();
+        let parent = Navigator::new(dir.path()).parent_item().unwrap();
+
+        assert!(parent.is_parent_link());
+        assert_eq!(parent.path, dir.path().parent().unwrap());
+        assert!(Navigator::new(Path::new("/")).parent_item().is_none());
+    }
+
+    #[test]
+    fn entry_lists_a_single_path() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), "abc").unwrap();
+
+        let item = Navigator::entry(&dir.path().join("a")).unwrap();
+        assert_eq!(item.display_name(), "a");
+        assert_eq!(item.size, Some(3));
+        assert!(Navigator::entry(&dir.path().join("gone")).is_none());
+    }
+
+    #[test]
+    fn listing_fails_up_front_and_then_yields_entries_lazily() {
+        let dir = tempdir().unwrap();
+        assert!(matches!(
+            Navigator::new(&dir.path().join("missing")).open_listing(),
+            Err(ListError::NotFound(_))
+        ));
+
+        for name in ["a", "b", "c"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        let mut listing = Navigator::new(dir.path()).open_listing().unwrap();
+        let first: Vec<_> = listing.by_ref().take(2).collect();
+        assert_eq!(first.len(), 2);
+        assert_eq!(listing.count(), 1);
+    }
+}
@@ -0,0 +1,403 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use std::{fs, io, thread};
+use log::info;
+use tokio::sync::mpsc;
+use crate::file_system::file_mutator::{CopyObserver, FileMutator};
+use crate::file_system::journal::JournalOp;
+use crate::file_system::trash::Trash;
+use crate::model::conflict::{ConflictPolicy, LinkPolicy, TransferKind, TransferOutcome};
+
+pub type JobId = u64;
+
+const UPDATE_INTERVAL: Duration = Duration::from_millis(50);
+const PAUSE_POLL: Duration = Duration::from_millis(100);
+
+/// A single step of a job. Conflicts have been resolved by the time a step
+/// is queued, so each carries the policy to apply.
+pub enum Operation {
+    Transfer {
+        src: PathBuf,
+        dst: PathBuf,
+        kind: TransferKind,
+        policy: ConflictPolicy,
+        links: LinkPolicy,
+    },
+    Restore {
+        trash: Trash,
+        trashed: PathBuf,
+        original: PathBuf,
+        policy: ConflictPolicy,
+    },
+    Trash {
+        trash: Trash,
+        path: PathBuf,
+    },
+    Delete {
+        path: PathBuf,
+    },
+    Purge {
+        trash: Trash,
+        trashed: PathBuf,
+    },
+}
+
+impl Operation {
+    fn source(&self) -> &Path {
+        match self {
+            Operation::Transfer { src, .. } => src,
+            Operation::Restore { trashed, .. } | Operation::Purge { trashed, .. } => trashed,
+            Operation::Trash { path, .. } | Operation::Delete { path } => path,
+        }
+    }
+
+    /// Whether the step reads file contents, i.e. whether byte counts matter.
+    fn copies(&self) -> bool {
+        matches!(self, Operation::Transfer { .. } | Operation::Restore { .. })
+    }
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct JobProgress {
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+    pub current: PathBuf,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Cancelled,
+    /// Finished, but some steps failed.
+    Failed(Vec<String>),
+}
+
+enum JobUpdate {
+    Started(JobId),
+    Progress(JobId, JobProgress),
+    Recorded(JournalOp),
+    Finished(JobId, JobState),
+}
+
+#[derive(Default)]
+struct JobControl {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+struct QueuedJob {
+    id: JobId,
+    operations: Vec<Operation>,
+    control: Arc<JobControl>,
+}
+
+/// UI-side view of a job.
+pub struct JobStatus {
+    pub id: JobId,
+    pub title: String,
+    pub state: JobState,
+    pub progress: JobProgress,
+    started: Option<Instant>,
+    control: Arc<JobControl>,
+}
+
+impl JobStatus {
+    pub fn is_paused(&self) -> bool {
+        self.control.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, JobState::Queued | JobState::Running)
+    }
+
+    /// Done fraction, by bytes when there are any, by files otherwise.
+    pub fn fraction(&self) -> f32 {
+        let p = &self.progress;
+        match (p.bytes_total, p.files_total) {
+            (0, 0) => 0.0,
+            (0, files) => p.files_done as f32 / files as f32,
+            (bytes, _) => p.bytes_done as f32 / bytes as f32,
+        }
+    }
+
+    /// Linear estimate from the rate so far.
+    pub fn eta(&self) -> Option<Duration> {
+        let elapsed = self.started?.elapsed();
+        let fraction = self.fraction() as f64;
+        if self.state != JobState::Running || fraction <= 0.0 {
+            return None;
+        }
+        Some(elapsed.mul_f64((1.0 - fraction) / fraction))
+    }
+}
+
+/// Runs file operations one job at a time on a background worker so the UI
+/// stays responsive, with progress, pause and cancel.
+pub struct OperationManager {
+    queue: mpsc::UnboundedSender<QueuedJob>,
+    updates: mpsc::UnboundedReceiver<JobUpdate>,
+    jobs: Vec<JobStatus>,
+    recorded: Vec<JournalOp>,
+    next_id: JobId,
+}
+
+impl OperationManager {
+    /// Must be called within a tokio runtime.
+    pub fn new() -> Self {
+        let (queue, mut jobs_rx) = mpsc::unbounded_channel::<QueuedJob>();
+        let (updates_tx, updates) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Some(job) = jobs_rx.recv().await {
+                let updates = updates_tx.clone();
+                let _ = tokio::task::spawn_blocking(move || Self::run(job, &updates)).await;
+            }
+        });
+
+        OperationManager { queue, updates, jobs: Vec::new(), recorded: Vec::new(), next_id: 1 }
+    }
+
+    pub fn submit(&mut self, title: String, operations: Vec<Operation>) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let control = Arc::new(JobControl::default());
+        self.jobs.push(JobStatus {
+            id,
+            title,
+            state: JobState::Queued,
+            progress: JobProgress::default(),
+            started: None,
+            control: control.clone(),
+        });
+        let _ = self.queue.send(QueuedJob { id, operations, control });
+        id
+    }
+
+    /// Applies updates from the worker. Returns the jobs that finished since
+    /// the last call; successful ones are dropped from the list.
+    pub fn poll(&mut self) -> Vec<JobId> {
+        let mut finished = Vec::new();
+
+        while let Ok(update) = self.updates.try_recv() {
+            match update {
+                JobUpdate::Started(id) => {
+                    if let Some(job) = self.job_mut(id) {
+                        job.state = JobState::Running;
+                        job.started = Some(Instant::now());
+                    }
+                }
+                JobUpdate::Progress(id, progress) => {
+                    if let Some(job) = self.job_mut(id) {
+                        job.progress = progress;
+                    }
+                }
+                JobUpdate::Recorded(op) => self.recorded.push(op),
+                JobUpdate::Finished(id, state) => {
+                    if let Some(job) = self.job_mut(id) {
+                        job.state = state;
+                    }
+                    finished.push(id);
+                }
+            }
+        }
+
+        self.jobs.retain(|job| job.state != JobState::Done);
+        finished
+    }
+
+    /// Moves and trashings completed since the last call, for the journal.
+    pub fn take_recorded(&mut self) -> Vec<JournalOp> {
+        std::mem::take(&mut self.recorded)
+    }
+
+    pub fn jobs(&self) -> &[JobStatus] {
+        &self.jobs
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.jobs.iter().any(JobStatus::is_active)
+    }
+
+    pub fn set_paused(&self, id: JobId, paused: bool) {
+        if let Some(job) = self.jobs.iter().find(|job| job.id == id) {
+            job.control.paused.store(paused, Ordering::Relaxed);
+        }
+    }
+
+    pub fn cancel(&self, id: JobId) {
+        if let Some(job) = self.jobs.iter().find(|job| job.id == id) {
+            job.control.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Forgets a finished job.
+    pub fn dismiss(&mut self, id: JobId) {
+        self.jobs.retain(|job| job.id != id || job.is_active());
+    }
+
+    fn job_mut(&mut self, id: JobId) -> Option<&mut JobStatus> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    fn run(job: QueuedJob, updates: &mpsc::UnboundedSender<JobUpdate>) {
+        let _ = updates.send(JobUpdate::Started(job.id));
+
+        let mut progress = JobProgress::default();
+        let sizes: Vec<(u64, u64)> = job.operations.iter()
+            .map(|op| {
+                let (bytes, files) = Self::measure(op.source());
+                (if op.copies() { bytes } else { 0 }, files)
+            })
+            .collect();
+        progress.bytes_total = sizes.iter().map(|(bytes, _)| bytes).sum();
+        progress.files_total = sizes.iter().map(|(_, files)| files).sum();
+
+        let mut observer = JobObserver {
+            id: job.id,
+            control: &job.control,
+            updates,
+            progress,
+            last_sent: Instant::now(),
+        };
+        let mut errors = Vec::new();
+        let (mut moved, mut trashed) = (Vec::new(), Vec::new());
+        let (mut bytes_after, mut files_after) = (0, 0);
+
+        for (op, (bytes, files)) in job.operations.iter().zip(sizes) {
+            if observer.check_control().is_err() {
+                break;
+            }
+
+            observer.progress.current = op.source().to_path_buf();
+            observer.send(true);
+
+            match Self::run_operation(op, &mut observer) {
+                Ok(Some(done)) => match op {
+                    Operation::Trash { .. } => trashed.push((op.source().to_path_buf(), done)),
+                    _ => moved.push((op.source().to_path_buf(), done)),
+                },
+                Ok(None) => {}
+                Err(_) if job.control.cancelled.load(Ordering::Relaxed) => break,
+                Err(e) => {
+                    info!("Job {} failed on {:?}: {}", job.id, op.source(), e);
+                    errors.push(format!("{}: {}", op.source().display(), e));
+                }
+            }
+
+            // renames and deletes finish without reporting bytes
+            bytes_after += bytes;
+            files_after += files;
+            observer.progress.bytes_done = bytes_after;
+            observer.progress.files_done = files_after;
+            observer.send(true);
+        }
+
+        // whatever completed is undoable, even if the job was cut short
+        if !moved.is_empty() {
+            let _ = updates.send(JobUpdate::Recorded(JournalOp::Move(moved)));
+        }
+        if !trashed.is_empty() {
+            let _ = updates.send(JobUpdate::Recorded(JournalOp::Trash(trashed)));
+        }
+
+        let state = if job.control.cancelled.load(Ordering::Relaxed) {
+            JobState::Cancelled
+        } else if errors.is_empty() {
+            JobState::Done
+        } else {
+            JobState::Failed(errors)
+        };
+        let _ = updates.send(JobUpdate::Finished(job.id, state));
+    }
+
+    /// Runs one step. Returns where a moved or trashed entry ended up.
+    fn run_operation(op: &Operation, observer: &mut JobObserver) -> io::Result<Option<PathBuf>> {
+        match op {
+            Operation::Transfer { src, dst, kind, policy, links } => {
+                match FileMutator::transfer_observed(src, dst, *kind, *policy, *links, observer)? {
+                    TransferOutcome::Transferred(dst) if *kind == TransferKind::Move => return Ok(Some(dst)),
+                    TransferOutcome::Conflict => return Err(Self::appeared(dst)),
+                    _ => {}
+                }
+            }
+            Operation::Restore { trash, trashed, original, policy } => {
+                if trash.restore(trashed, original, *policy)? == TransferOutcome::Conflict {
+                    return Err(Self::appeared(original));
+                }
+            }
+            Operation::Trash { trash, path } => return trash.trash(path).map(Some),
+            Operation::Delete { path } => FileMutator::durable_remove(path)?,
+            Operation::Purge { trash, trashed } => trash.purge(trashed)?,
+        }
+        Ok(None)
+    }
+
+    /// A destination that was free when the job was planned but is taken by
+    /// the time the job gets to it. The step fails rather than asking, as
+    /// nobody is there to answer.
+    fn appeared(dst: &Path) -> io::Error {
+        io::Error::new(io::ErrorKind::AlreadyExists, format!("{} appeared in the meantime", dst.display()))
+    }
+
+    /// Total bytes and file count of a file or tree, without following links.
+    pub(crate) fn measure(path: &Path) -> (u64, u64) {
+        let Ok(metadata) = fs::symlink_metadata(path) else { return (0, 0) };
+        if !metadata.is_dir() {
+            return (metadata.len(), 1);
+        }
+
+        fs::read_dir(path).into_iter().flatten().flatten()
+            .map(|entry| Self::measure(&entry.path()))
+            .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f))
+    }
+}
+
+struct JobObserver<'a> {
+    id: JobId,
+    control: &'a JobControl,
+    updates: &'a mpsc::UnboundedSender<JobUpdate>,
+    progress: JobProgress,
+    last_sent: Instant,
+}
+
+impl JobObserver<'_> {
+    /// Blocks while paused; fails once cancelled.
+    fn check_control(&self) -> io::Result<()> {
+        while self.control.paused.load(Ordering::Relaxed) && !self.control.cancelled.load(Ordering::Relaxed) {
+            thread::sleep(PAUSE_POLL);
+        }
+
+        if self.control.cancelled.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, force: bool) {
+        if force || self.last_sent.elapsed() >= UPDATE_INTERVAL {
+            let _ = self.updates.send(JobUpdate::Progress(self.id, self.progress.clone()));
+            self.last_sent = Instant::now();
+        }
+    }
+}
+
+impl CopyObserver for JobObserver<'_> {
+    fn bytes_copied(&mut self, bytes: u64) -> io::Result<()> {
+        self.progress.bytes_done += bytes;
+        self.send(false);
+        self.check_control()
+    }
+
+    fn file_copied(&mut self) -> io::Result<()> {
+        self.progress.files_done += 1;
+        self.send(false);
+        self.check_control()
+    }
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tempfile::tempdir;
+    use crate::file_system::operations::{JobId, JobState, Operation, OperationManager};
+    use crate::model::conflict::{ConflictPolicy, LinkPolicy, TransferKind};
+
+    async fn wait_for(manager: &mut OperationManager, id: JobId) -> Option<JobState> {
+        for _ in 0..500 {
+            if manager.poll().contains(&id) {
+                return Some(manager.jobs().iter()
+                    .find(|job| job.id == id)
+                    .map(|job| job.state.clone())
+                    .unwrap_or(JobState::Done));
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        None
+    }
+
+    fn transfer(src: PathBuf, dst: PathBuf, kind: TransferKind) -> Operation {
+        Operation::Transfer { src, dst, kind, policy: ConflictPolicy::Ask, links: LinkPolicy::Preserve }
+    }
+
+    #[test]
+    fn measure_counts_files_and_bytes_of_tree() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("a/one"), "12345").unwrap();
+        fs::write(dir.path().join("a/b/two"), "123").unwrap();
+
+        assert_eq!(OperationManager::measure(&dir.path().join("a")), (8, 2));
+        assert_eq!(OperationManager::measure(&dir.path().join("missing")), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn job_runs_in_background_and_is_dropped_when_done() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dst = dir.path().join("dst");
+        fs::create_dir_all(&dst).unwrap();
+        fs::create_dir_all(src.join("tree")).unwrap();
+        fs::write(src.join("tree/file"), "data").unwrap();
+        fs::write(src.join("single"), "x").unwrap();
+
+        let mut manager = OperationManager::new();
+        let id = manager.submit("Copying".into(), vec![
+            transfer(src.join("tree"), dst.join("tree"), TransferKind::Copy),
+            transfer(src.join("single"), dst.join("single"), TransferKind::Move),
+        ]);
+        assert!(manager.is_busy());
+
+        assert_eq!(wait_for(&mut manager, id).await, Some(JobState::Done));
+        assert!(manager.jobs().is_empty());
+        assert!(!manager.is_busy());
+        assert_eq!(fs::read_to_string(dst.join("tree/file")).unwrap(), "data");
+        assert!(src.join("tree/file").exists());
+        assert!(dst.join("single").exists());
+        assert!(!src.join("single").exists());
+    }
+
+    #[tokio::test]
+    async fn failed_steps_are_reported_and_others_still_run() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("ok"), "ok").unwrap();
+
+        let mut manager = OperationManager::new();
+        let id = manager.submit("Deleting".into(), vec![
+            Operation::Delete { path: dir.path().join("missing") },
+            Operation::Delete { path: dir.path().join("ok") },
+        ]);
+
+        let Some(JobState::Failed(errors)) = wait_for(&mut manager, id).await else {
+            panic!("job should have failed");
+        };
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("missing"));
+        assert!(!dir.path().join("ok").exists());
+
+        manager.dismiss(id);
+        assert!(manager.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn destinations_taken_meanwhile_fail_the_job() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("src"), "new").unwrap();
+        fs::write(dir.path().join("dst"), "old").unwrap();
+
+        let mut manager = OperationManager::new();
+        let id = manager.submit("Copying".into(), vec![
+            transfer(dir.path().join("src"), dir.path().join("dst"), TransferKind::Copy),
+        ]);
+
+        let Some(JobState::Failed(errors)) = wait_for(&mut manager, id).await else {
+            panic!("job should have failed");
+        };
+        assert!(errors[0].contains("appeared in the meantime"));
+        assert_eq!(manager.jobs().len(), 1);
+        assert_eq!(fs::read_to_string(dir.path().join("dst")).unwrap(), "old");
+    }
+
+    #[tokio::test]
+    async fn cancelled_job_leaves_sources_alone() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("file");
+        fs::write(&src, "keep").unwrap();
+
+        // the test runtime is single threaded, so the worker cannot start
+        // before the first await
+        let mut manager = OperationManager::new();
+        let id = manager.submit("Moving".into(), vec![
+            transfer(src.clone(), dir.path().join("moved"), TransferKind::Move),
+        ]);
+        manager.cancel(id);
+
+        assert_eq!(wait_for(&mut manager, id).await, Some(JobState::Cancelled));
+        assert!(src.exists());
+        assert!(!dir.path().join("moved").exists());
+    }
+
+    #[tokio::test]
+    async fn paused_job_waits_until_resumed() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("file");
+        fs::write(&src, "data").unwrap();
+
+        let mut manager = OperationManager::new();
+        let id = manager.submit("Copying".into(), vec![
+            transfer(src.clone(), dir.path().join("copy"), TransferKind::Copy),
+        ]);
+        manager.set_paused(id, true);
+        assert!(manager.jobs()[0].is_paused());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        manager.poll();
+        assert!(manager.is_busy());
+        assert!(!dir.path().join("copy").exists());
+
+        manager.set_paused(id, false);
+        assert_eq!(wait_for(&mut manager, id).await, Some(JobState::Done));
+        assert!(dir.path().join("copy").exists());
+    }
+}
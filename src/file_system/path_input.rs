@@ -0,0 +1,117 @@
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::{env, fs};
+use crate::model::file_name;
+
+/// Result of completing a typed path.
+#[derive(Debug, PartialEq)]
+pub struct Completion {
+    /// The input, extended as far as the matches agree.
+    pub text: String,
+    /// Names still matching when there is more than one.
+    pub candidates: Vec<String>,
+}
+
+/// Turns typed text into a path: a leading `~` becomes the home directory,
+/// `$VAR` and `${VAR}` the variable's value, and relative paths are taken
+/// from `base`. The rest is read as escaped by `file_name::escape`. `.` and
+/// `..` are resolved without looking at the disk.
+pub fn expand(input: &str, base: &Path) -> Result<PathBuf, String> {
+    expand_with(input, base, dirs::home_dir().as_deref(), |name| env::var_os(name))
+}
+
+pub(crate) fn expand_with(
+    input: &str,
+    base: &Path,
+    home: Option<&Path>,
+    var: impl Fn(&str) -> Option<OsString>,
+) -> Result<PathBuf, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Enter a path".into());
+    }
+
+    let mut expanded = OsString::new();
+    let mut rest = input;
+    if input == "~" || input.starts_with("~/") {
+        expanded.push(home.ok_or("No home directory to expand ~ to")?);
+        rest = &input[1..];
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push(file_name::unescape(&rest[..dollar]));
+        let after = &rest[dollar + 1..];
+        let (name, next) = match after.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced.find('}').ok_or("Missing } after ${")?;
+                (&braced[..end], &braced[end + 1..])
+            }
+            None => {
+                let end = after.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        if name.is_empty() {
+            // a lone `$` is part of the name
+            expanded.push("$");
+        } else {
+            expanded.push(var(name).ok_or_else(|| format!("${} is not set", name))?);
+        }
+        rest = next;
+    }
+    expanded.push(file_name::unescape(rest));
+
+    Ok(normalize(&base.join(expanded)))
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+/// Completes the last component of `input` to the directories it can be,
+/// their names escaped as in `expand`. A single match gets a trailing `/`;
+/// hidden directories only match once the component starts with a dot.
+pub fn complete(input: &str, base: &Path) -> Completion {
+    let (dir, prefix) = match input.rfind('/') {
+        Some(slash) => input.split_at(slash + 1),
+        None if input == "~" => ("~/", ""),
+        None => ("", input),
+    };
+    let dir_path = if dir.is_empty() { Ok(base.to_path_buf()) } else { expand(dir, base) };
+
+    let mut names: Vec<String> = dir_path.ok()
+        .and_then(|path| fs::read_dir(path).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| file_name::escape(&entry.file_name()))
+        .filter(|name| name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.')))
+        .collect();
+    names.sort();
+
+    match names.as_slice() {
+        [] => Completion { text: input.to_string(), candidates: Vec::new() },
+        [name] => Completion { text: format!("{}{}/", dir, name), candidates: Vec::new() },
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.as_str(), |common, name| {
+                let end = common.char_indices()
+                    .zip(name.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map_or(common.len().min(name.len()), |((i, _), _)| i);
+                &common[..end]
+            });
+            Completion { text: format!("{}{}", dir, common), candidates: names.clone() }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+    use crate::file_system::path_input::{complete, expand_with, Completion};
+
+    fn expand(input: &str) -> Result<PathBuf, String> {
+        let var = |name: &str| match name {
+            "DATA" => Some(OsString::from("/srv/data")),
+            "USER" => Some(OsString::from("ann")),
+            _ => None,
+        };
+        expand_with(input, Path::new("/base"), Some(Path::new("/home/ann")), var)
+    }
+
+    #[test]
+    fn expands_home_and_variables() {
+        assert_eq!(expand("~").unwrap(), Path::new("/home/ann"));
+        assert_eq!(expand("~/docs").unwrap(), Path::new("/home/ann/docs"));
+        assert_eq!(expand("$DATA/x").unwrap(), Path::new("/srv/data/x"));
+        assert_eq!(expand("/home/${USER}_old").unwrap(), Path::new("/home/ann_old"));
+        assert_eq!(expand("/tmp/cost$").unwrap(), Path::new("/tmp/cost$"));
+        assert_eq!(expand("~ann").unwrap(), Path::new("/base/~ann"));
+    }
+
+    #[test]
+    fn relative_paths_start_at_the_base() {
+        assert_eq!(expand("sub/./dir").unwrap(), Path::new("/base/sub/dir"));
+        assert_eq!(expand("../other").unwrap(), Path::new("/other"));
+        assert_eq!(expand("/../..").unwrap(), Path::new("/"));
+    }
+
+    #[test]
+    fn unusable_input_is_explained() {
+        assert_eq!(expand("$NOPE/x").unwrap_err(), "$NOPE is not set");
+        assert!(expand("${DATA").is_err());
+        assert!(expand("  ").is_err());
+    }
+
+    #[test]
+    fn completes_directories() {
+        let dir = tempdir().unwrap();
+        for name in ["music", "movies", "mail", ".mozilla"] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        fs::write(dir.path().join("mozart.txt"), "").unwrap();
+
+        assert_eq!(complete("mu", dir.path()), Completion { text: "music/".into(), candidates: Vec::new() });
+        assert_eq!(complete("mo", dir.path()), Completion { text: "movies/".into(), candidates: Vec::new() });
+        assert_eq!(complete(".m", dir.path()).text, ".mozilla/");
+        assert_eq!(complete("x", dir.path()).text, "x");
+
+        let all = complete("m", dir.path());
+        assert_eq!(all.candidates, ["mail", "movies", "music"]);
+        assert_eq!(all.text, "m");
+
+        let absolute = format!("{}/mu", dir.path().display());
+        assert_eq!(complete(&absolute, Path::new("/")).text, format!("{}/music/", dir.path().display()));
+    }
+
+    #[test]
+    fn names_that_are_not_utf8_complete_and_expand_escaped() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        let name = std::ffi::OsStr::from_bytes(b"caf\xe9");
+        fs::create_dir(dir.path().join(name)).unwrap();
+        fs::create_dir(dir.path().join("back\\slash")).unwrap();
+
+        let completed = complete("caf", dir.path()).text;
+        assert_eq!(completed, "caf\\xE9/");
+        assert_eq!(expand_with(&completed, dir.path(), None, |_| None).unwrap(), dir.path().join(name));
+
+        let completed = complete("back", dir.path()).text;
+        assert_eq!(completed, "back\\\\slash/");
+        assert_eq!(expand_with(&completed, dir.path(), None, |_| None).unwrap(), dir.path().join("back\\slash"));
+    }
+}
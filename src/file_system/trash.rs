@@ -0,0 +1,281 @@
+use std::ffi::{OsStr, OsString};
+use std::fs::DirBuilder;
+use std::io::Write;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use crate::file_system::file_mutator::FileMutator;
+use crate::model::TrashInfo;
+use crate::model::conflict::{ConflictPolicy, TransferKind, TransferOutcome};
+
+/// Trash following the freedesktop.org Trash specification: the home trash
+/// for everything on the same filesystem, `$topdir/.Trash/$uid` or
+/// `$topdir/.Trash-$uid` for items on other mounts.
+#[derive(Clone)]
+pub struct Trash {
+    home: PathBuf,
+}
+
+pub struct TrashEntry {
+    /// Where the item currently lives, inside `files/`.
+    pub path: PathBuf,
+    pub info: TrashInfo,
+}
+
+impl Trash {
+    /// Home trash at `$XDG_DATA_HOME/Trash`, `~/.local/share/Trash` if unset.
+    pub fn new() -> Self {
+        let data_home = env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .unwrap_or_else(|| {
+                dirs::home_dir().expect("Could not find home directory").join(".local/share")
+            });
+
+        Self::with_root(data_home.join("Trash"))
+    }
+
+    pub fn with_root(home: PathBuf) -> Self {
+        Trash { home }
+    }
+
+    #[cfg(test)]
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    pub fn files_dir(&self) -> PathBuf {
+        self.home.join("files")
+    }
+
+    fn info_dir(&self) -> PathBuf {
+        self.home.join("info")
+    }
+
+    pub fn ensure_dirs(&self) -> io::Result<()> {
+        for dir in [self.home.clone(), self.files_dir(), self.info_dir()] {
+            DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Everything in the home trash that has both a `.trashinfo` record and
+    /// a matching entry in `files/`.
+    pub fn list(&self) -> io::Result<Vec<TrashEntry>> {
+        let read_dir = match fs::read_dir(self.info_dir()) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries = Vec::new();
+        for entry in read_dir {
+            let info_path = entry?.path();
+            if info_path.extension() != Some(OsStr::new("trashinfo")) {
+                continue;
+            }
+
+            let path = self.files_dir().join(info_path.file_stem().unwrap());
+            if fs::symlink_metadata(&path).is_err() {
+                continue;
+            }
+
+            if let Some(info) = fs::read(&info_path).ok().and_then(|data| Self::parse_trashinfo(&data)) {
+                entries.push(TrashEntry { path, info });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Moves a trashed item back to `original_path` and drops its record.
+    /// Conflicts at the original location follow `policy`.
+    pub fn restore(&self, trashed: &Path, original_path: &Path, policy: ConflictPolicy) -> io::Result<TransferOutcome> {
+        if let Some(parent) = original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let outcome = FileMutator::transfer(trashed, original_path, TransferKind::Move, policy)?;
+        if let TransferOutcome::Transferred(_) = outcome {
+            self.remove_record(trashed)?;
+        }
+        Ok(outcome)
+    }
+
+    /// Deletes a trashed item for good.
+    pub fn purge(&self, trashed: &Path) -> io::Result<()> {
+        FileMutator::durable_remove(trashed)?;
+        self.remove_record(trashed)
+    }
+
+    /// Records live in `info/` next to the `files/` directory holding the
+    /// item, which is not the home trash for items from other mounts.
+    fn remove_record(&self, trashed: &Path) -> io::Result<()> {
+        let info_dir = trashed.parent().and_then(Path::parent)
+            .map(|trash_dir| trash_dir.join("info"))
+            .unwrap_or_else(|| self.info_dir());
+        let mut info_name = trashed.file_name().unwrap_or_default().to_os_string();
+        info_name.push(".trashinfo");
+
+        fs::remove_file(info_dir.join(info_name))?;
+        fs::File::open(info_dir)?.sync_all()
+    }
+
+    pub(crate) fn parse_trashinfo(data: &[u8]) -> Option<TrashInfo> {
+        let text = String::from_utf8_lossy(data);
+        let lines = text.lines().map(str::trim).skip_while(|l| *l != "[Trash Info]").skip(1);
+
+        let mut original_path = None;
+        let mut deleted = None;
+        for line in lines.take_while(|l| !l.starts_with('[')) {
+            if let Some(value) = line.strip_prefix("Path=") {
+                original_path = Some(PathBuf::from(OsString::from_vec(Self::decode_path(value))));
+            } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                deleted = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+                    .ok()
+                    .and_then(|dt| Local.from_local_datetime(&dt).earliest());
+            }
+        }
+
+        Some(TrashInfo { original_path: original_path?, deleted })
+    }
+
+    /// Moves `path` into the trash and returns where it ended up.
+    pub fn trash<T: AsRef<Path>>(&self, path: T) -> io::Result<PathBuf> {
+        let path = std::path::absolute(path.as_ref())?;
+        let file_name = path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("cannot trash {:?}", path))
+        })?;
+
+        if path.starts_with(&self.home) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} is already in the trash", path)));
+        }
+
+        let (trash_dir, topdir) = self.trash_dir_for(&path)?;
+        let files = trash_dir.join("files");
+        let info = trash_dir.join("info");
+        for dir in [&trash_dir, &files, &info] {
+            DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        }
+
+        // the name counts as taken once its .trashinfo has been created
+        let (name, info_path) = Self::reserve_name(&files, &info, file_name)?;
+
+        let recorded_path = match &topdir {
+            Some(topdir) => path.strip_prefix(topdir).unwrap_or(&path),
+            None => &path,
+        };
+        let trashinfo = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            Self::encode_path(recorded_path),
+            Local::now().format("%Y-%m-%dT%H:%M:%S"),
+        );
+
+        let written = fs::OpenOptions::new().write(true).open(&info_path)
+            .and_then(|mut f| {
+                f.write_all(trashinfo.as_bytes())?;
+                f.sync_all()
+            });
+
+        let trashed = files.join(&name);
+        if let Err(e) = written.and_then(|_| FileMutator::durable_move(&path, &trashed)) {
+            let _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
+        fs::File::open(&info)?.sync_all()?;
+        Ok(trashed)
+    }
+
+    /// The trash directory to use for `path`, plus the mount's top directory
+    /// when it is not the home trash.
+    fn trash_dir_for(&self, path: &Path) -> io::Result<(PathBuf, Option<PathBuf>)> {
+        let item_dev = fs::symlink_metadata(path)?.dev();
+        let home_dev = Self::nearest_existing(&self.home)?.dev();
+
+        if item_dev == home_dev {
+            return Ok((self.home.clone(), None));
+        }
+
+        let mut topdir = path.parent().unwrap_or(path);
+        while let Some(parent) = topdir.parent() {
+            match fs::metadata(parent) {
+                Ok(m) if m.dev() == item_dev => topdir = parent,
+                _ => break,
+            }
+        }
+
+        // SAFETY: `getuid` takes no arguments and cannot fail.
+        let uid = unsafe { libc::getuid() };
+        let shared = topdir.join(".Trash");
+        let trash_dir = match fs::symlink_metadata(&shared) {
+            Ok(m) if m.is_dir() && m.permissions().mode() & 0o1000 != 0 => shared.join(uid.to_string()),
+            _ => topdir.join(format!(".Trash-{}", uid)),
+        };
+        Ok((trash_dir, Some(topdir.to_path_buf())))
+    }
+
+    fn nearest_existing(path: &Path) -> io::Result<fs::Metadata> {
+        path.ancestors()
+            .find_map(|p| fs::metadata(p).ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{:?} does not exist", path)))
+    }
+
+    fn reserve_name(files: &Path, info: &Path, file_name: &OsStr) -> io::Result<(std::ffi::OsString, PathBuf)> {
+        for n in 0.. {
+            let name = match n {
+                0 => file_name.to_os_string(),
+                n => FileMutator::numbered_name(file_name, n),
+            };
+            if fs::symlink_metadata(files.join(&name)).is_ok() {
+                continue;
+            }
+
+            let mut info_name = name.clone();
+            info_name.push(".trashinfo");
+            let info_path = info.join(info_name);
+
+            match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(_) => return Ok((name, info_path)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+
+    pub(crate) fn decode_path(value: &str) -> Vec<u8> {
+        let bytes = value.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let hex = bytes.get(i + 1..i + 3)
+                .and_then(|h| std::str::from_utf8(h).ok())
+                .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+            match (bytes[i], hex) {
+                (b'%', Some(byte)) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                (b, _) => {
+                    decoded.push(b);
+                    i += 1;
+                }
+            }
+        }
+        decoded
+    }
+
+    /// Percent-encodes everything but unreserved characters and `/`.
+    pub(crate) fn encode_path(path: &Path) -> String {
+        path.as_os_str().as_bytes().iter()
+            .map(|&b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                    (b as char).to_string()
+                }
+                _ => format!("%{:02X}", b),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,212 @@
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, path::PathBuf};
+    use tempfile::tempdir;
+    use crate::file_system::file_mutator::FileMutator;
+    use crate::file_system::trash::Trash;
+    use crate::model::conflict::{ConflictPolicy, TransferOutcome};
+
+    fn trashinfo(trash: &Trash, name: &str) -> String {
+        fs::read_to_string(trash.home().join("info").join(format!("{}.trashinfo", name))).unwrap()
+    }
+
+    #[test]
+    fn trash_moves_file_and_writes_trashinfo() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("data/Trash"));
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "remember").unwrap();
+
+        let trashed = trash.trash(&file).unwrap();
+
+        assert!(!file.exists());
+        assert_eq!(trashed, trash.home().join("files/notes.txt"));
+        assert_eq!(fs::read_to_string(&trashed).unwrap(), "remember");
+
+        let info = trashinfo(&trash, "notes.txt");
+        let mut lines = info.lines();
+        assert_eq!(lines.next(), Some("[Trash Info]"));
+        assert_eq!(lines.next(), Some(format!("Path={}", Trash::encode_path(&file)).as_str()));
+        assert!(lines.next().unwrap().starts_with("DeletionDate="));
+    }
+
+    #[test]
+    fn trash_directory_tree() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        let tree = dir.path().join("project");
+        fs::create_dir_all(tree.join("src")).unwrap();
+        fs::write(tree.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let trashed = trash.trash(&tree).unwrap();
+
+        assert!(!tree.exists());
+        assert_eq!(fs::read_to_string(trashed.join("src/main.rs")).unwrap(), "fn main() {}");
+    }
+
+    #[test]
+    fn trash_same_name_twice_keeps_both() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        let file = dir.path().join("a.log");
+
+        fs::write(&file, "first").unwrap();
+        let first = trash.trash(&file).unwrap();
+        fs::write(&file, "second").unwrap();
+        let second = trash.trash(&file).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(second, trash.home().join("files/a (1).log"));
+        assert_eq!(fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "second");
+        assert!(trashinfo(&trash, "a (1).log").starts_with("[Trash Info]"));
+    }
+
+    #[test]
+    fn trash_missing_file_leaves_no_trashinfo() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+
+        assert!(trash.trash(dir.path().join("ghost")).is_err());
+        assert!(!trash.home().join("info/ghost.trashinfo").exists());
+    }
+
+    #[test]
+    fn encode_path_escapes_reserved_bytes() {
+        assert_eq!(Trash::encode_path(Path::new("/home/me/My file%.txt")), "/home/me/My%20file%25.txt");
+        assert_eq!(Trash::encode_path(&PathBuf::from("/tmp/ä")), "/tmp/%C3%A4");
+    }
+
+    #[test]
+    fn durable_remove_deletes_tree() {
+        let dir = tempdir().unwrap();
+        let tree = dir.path().join("gone");
+        fs::create_dir_all(tree.join("deep/er")).unwrap();
+        fs::write(tree.join("deep/er/file"), "x").unwrap();
+
+        FileMutator::durable_remove(&tree).unwrap();
+
+        assert!(!tree.exists());
+        assert!(dir.path().exists());
+    }
+
+    #[test]
+    fn list_reads_original_path_and_date() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        let file = dir.path().join("with space.txt");
+        fs::write(&file, "x").unwrap();
+
+        let trashed = trash.trash(&file).unwrap();
+        let entries = trash.list().unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, trashed);
+        assert_eq!(entries[0].info.original_path, file);
+        assert!(entries[0].info.deleted.is_some());
+    }
+
+    #[test]
+    fn list_of_missing_trash_is_empty() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("never-created"));
+
+        assert!(trash.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn list_skips_records_without_files() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        trash.ensure_dirs().unwrap();
+        fs::write(
+            trash.home().join("info/orphan.trashinfo"),
+            "[Trash Info]\nPath=/tmp/orphan\nDeletionDate=2024-01-02T03:04:05\n",
+        ).unwrap();
+
+        assert!(trash.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_trashinfo_decodes_path() {
+        let info = Trash::parse_trashinfo(
+            b"[Trash Info]\nPath=/home/me/My%20file%25.txt\nDeletionDate=2024-01-02T03:04:05\n",
+        ).unwrap();
+
+        assert_eq!(info.original_path, PathBuf::from("/home/me/My file%.txt"));
+        assert_eq!(info.deleted.unwrap().format("%Y-%m-%d %H:%M:%S").to_string(), "2024-01-02 03:04:05");
+    }
+
+    #[test]
+    fn parse_trashinfo_without_path_is_rejected() {
+        assert!(Trash::parse_trashinfo(b"[Trash Info]\nDeletionDate=2024-01-02T03:04:05\n").is_none());
+        assert!(Trash::parse_trashinfo(b"Path=/outside/the/group\n").is_none());
+    }
+
+    #[test]
+    fn restore_returns_item_and_drops_record() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        let file = dir.path().join("nested/doc.txt");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "doc").unwrap();
+
+        let trashed = trash.trash(&file).unwrap();
+        fs::remove_dir(file.parent().unwrap()).unwrap();
+
+        let outcome = trash.restore(&trashed, &file, ConflictPolicy::Ask).unwrap();
+
+        assert_eq!(outcome, TransferOutcome::Transferred(file.clone()));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "doc");
+        assert!(trash.list().unwrap().is_empty());
+        assert!(!trash.home().join("info/doc.txt.trashinfo").exists());
+    }
+
+    #[test]
+    fn restore_onto_existing_file_follows_policy() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        let file = dir.path().join("doc.txt");
+        fs::write(&file, "trashed").unwrap();
+        let trashed = trash.trash(&file).unwrap();
+        fs::write(&file, "replacement").unwrap();
+
+        assert_eq!(trash.restore(&trashed, &file, ConflictPolicy::Ask).unwrap(), TransferOutcome::Conflict);
+        assert_eq!(trash.list().unwrap().len(), 1);
+
+        let outcome = trash.restore(&trashed, &file, ConflictPolicy::RenameWithSuffix).unwrap();
+
+        let renamed = dir.path().join("doc (1).txt");
+        assert_eq!(outcome, TransferOutcome::Transferred(renamed.clone()));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "replacement");
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), "trashed");
+        assert!(trash.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn purge_removes_file_and_record() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        let tree = dir.path().join("tree");
+        fs::create_dir_all(tree.join("a")).unwrap();
+
+        let trashed = trash.trash(&tree).unwrap();
+        trash.purge(&trashed).unwrap();
+
+        assert!(!trashed.exists());
+        assert!(trash.list().unwrap().is_empty());
+        assert!(!trash.home().join("info/tree.trashinfo").exists());
+    }
+
+    #[test]
+    fn trashing_inside_the_trash_is_refused() {
+        let dir = tempdir().unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        let file = dir.path().join("f");
+        fs::write(&file, "").unwrap();
+        let trashed = trash.trash(&file).unwrap();
+
+        assert!(trash.trash(&trashed).is_err());
+        assert!(trashed.exists());
+    }
+}
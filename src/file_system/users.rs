@@ -0,0 +1,57 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::ptr;
+
+thread_local! {
+    static USERS: RefCell<HashMap<u32, Option<String>>> = RefCell::new(HashMap::new());
+    static GROUPS: RefCell<HashMap<u32, Option<String>>> = RefCell::new(HashMap::new());
+}
+
+/// Name of user `uid`, looked up once per thread. `None` for unknown ids.
+pub fn user_name(uid: u32) -> Option<String> {
+    USERS.with(|users| users.borrow_mut().entry(uid).or_insert_with(|| lookup_user(uid)).clone())
+}
+
+/// Name of group `gid`, looked up once per thread. `None` for unknown ids.
+pub fn group_name(gid: u32) -> Option<String> {
+    GROUPS.with(|groups| groups.borrow_mut().entry(gid).or_insert_with(|| lookup_group(gid)).clone())
+}
+
+fn lookup_user(uid: u32) -> Option<String> {
+    with_buffer(|buf| {
+        // SAFETY: all pointers are valid for the call; on success `pw_name`
+        // points into `buf`, which is still alive when it is read.
+        unsafe {
+            let mut entry: libc::passwd = std::mem::zeroed();
+            let mut result = ptr::null_mut();
+            let rc = libc::getpwuid_r(uid, &mut entry, buf.as_mut_ptr(), buf.len(), &mut result);
+            (rc, (!result.is_null()).then(|| CStr::from_ptr(entry.pw_name).to_string_lossy().into_owned()))
+        }
+    })
+}
+
+fn lookup_group(gid: u32) -> Option<String> {
+    with_buffer(|buf| {
+        // SAFETY: as in `lookup_user`.
+        unsafe {
+            let mut entry: libc::group = std::mem::zeroed();
+            let mut result = ptr::null_mut();
+            let rc = libc::getgrgid_r(gid, &mut entry, buf.as_mut_ptr(), buf.len(), &mut result);
+            (rc, (!result.is_null()).then(|| CStr::from_ptr(entry.gr_name).to_string_lossy().into_owned()))
+        }
+    })
+}
+
+/// Retries `lookup` with a bigger buffer while it reports `ERANGE`.
+fn with_buffer(mut lookup: impl FnMut(&mut [libc::c_char]) -> (libc::c_int, Option<String>)) -> Option<String> {
+    let mut size = 1024;
+    loop {
+        let mut buf = vec![0; size];
+        match lookup(&mut buf) {
+            (libc::ERANGE, _) if size < 1 << 20 => size *= 2,
+            (0, name) => return name,
+            _ => return None,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#[cfg(test)]
+mod tests {
+    use crate::file_system::users::{group_name, user_name};
+
+    #[test]
+    fn root_has_a_name() {
+        assert_eq!(user_name(0).as_deref(), Some("root"));
+        assert!(group_name(0).is_some());
+    }
+
+    #[test]
+    fn unknown_ids_have_none() {
+        assert_eq!(user_name(3_999_999_999), None);
+        assert_eq!(group_name(3_999_999_999), None);
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::{Path, PathBuf};
 use std::time::Duration;
+use notify::event::{EventKind, ModifyKind, RenameMode};
 use notify::{
     RecommendedWatcher,
     RecursiveMode,
@@ -12,6 +13,51 @@ use tokio::sync::mpsc;
 use tokio::time;
 use crate::ui::file_pane::view::NavigatedEvent;
 
+/// A change to an entry of the watched directory.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileChange {
+    Created(PathBuf),
+    Removed(PathBuf),
+    Modified(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+impl FileChange {
+    /// Paths whose entries may look different now.
+    pub fn paths(&self) -> Vec<&Path> {
+        match self {
+            FileChange::Created(path) | FileChange::Removed(path) | FileChange::Modified(path) => vec![path],
+            FileChange::Renamed { from, to } => vec![from, to],
+        }
+    }
+
+    /// Translates a `notify` event. `None` means the event says too little
+    /// and the directory has to be listed again.
+    pub fn from_event(event: &NotifyEvent) -> Option<Vec<FileChange>> {
+        if event.need_rescan() {
+            return None;
+        }
+
+        let each = |change: fn(PathBuf) -> FileChange| Some(event.paths.iter().cloned().map(change).collect());
+        match event.kind {
+            EventKind::Create(_) => each(FileChange::Created),
+            EventKind::Remove(_) => each(FileChange::Removed),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => match event.paths.as_slice() {
+                [from, to] => Some(vec![FileChange::Renamed { from: from.clone(), to: to.clone() }]),
+                _ => None,
+            },
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => each(FileChange::Removed),
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => each(FileChange::Created),
+            EventKind::Modify(_) => each(FileChange::Modified),
+            EventKind::Access(_) => Some(Vec::new()),
+            EventKind::Any | EventKind::Other => None,
+        }
+    }
+}
+
+/// Past this many changes in one batch, listing again is cheaper.
+const MAX_CHANGES: usize = 4096;
+
 pub struct FileWatcher {
     pub _sender: mpsc::Sender<NavigatedEvent>,
     _watcher: RecommendedWatcher,
@@ -38,16 +84,28 @@ impl FileWatcher {
         tokio::spawn(async move {
             let debounce = Duration::from_millis(100);
             let mut pending = false;
+            let mut changes = Vec::new();
+            let mut rescan = false;
 
             loop {
                 tokio::select! {
                     maybe_evt = notify_rx.recv() => {
                         match maybe_evt {
-                            Some(Ok(_evt)) => {
-                                pending = true;
-                            }
+                            Some(Ok(evt)) => match FileChange::from_event(&evt) {
+                                Some(new_changes) if !new_changes.is_empty() => {
+                                    changes.extend(new_changes);
+                                    rescan |= changes.len() > MAX_CHANGES;
+                                    pending = true;
+                                }
+                                Some(_) => {}
+                                None => {
+                                    rescan = true;
+                                    pending = true;
+                                }
+                            },
                             Some(Err(err)) => {
                                 eprintln!("[file-watcher] error: {err}");
+                                rescan = true;
                                 pending = true;
                             }
                             None => {
@@ -56,10 +114,17 @@ impl FileWatcher {
                         }
                     }
                     _ = time::sleep(debounce), if pending => {
-                        if pane_sender.send(NavigatedEvent::FilesUpdated).await.is_err() {
+                        let event = if rescan {
+                            changes.clear();
+                            NavigatedEvent::FilesUpdated
+                        } else {
+                            NavigatedEvent::FilesChanged(std::mem::take(&mut changes))
+                        };
+                        if pane_sender.send(event).await.is_err() {
                             break;
                         }
                         pending = false;
+                        rescan = false;
                     }
                 }
             }
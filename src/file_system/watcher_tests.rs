@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use notify::event::{CreateKind, DataChange, EventKind, Flag, ModifyKind, RemoveKind, RenameMode};
+    use notify::Event;
+    use crate::file_system::watcher::FileChange;
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        paths.iter().fold(Event::new(kind), |event, path| event.add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn typed_events_become_changes() {
+        assert_eq!(
+            FileChange::from_event(&event(EventKind::Create(CreateKind::File), &["/d/a"])),
+            Some(vec![FileChange::Created("/d/a".into())])
+        );
+        assert_eq!(
+            FileChange::from_event(&event(EventKind::Remove(RemoveKind::Any), &["/d/a"])),
+            Some(vec![FileChange::Removed("/d/a".into())])
+        );
+        assert_eq!(
+            FileChange::from_event(&event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), &["/d/a"])),
+            Some(vec![FileChange::Modified("/d/a".into())])
+        );
+        assert_eq!(
+            FileChange::from_event(&event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/d/a", "/d/b"])),
+            Some(vec![FileChange::Renamed { from: "/d/a".into(), to: "/d/b".into() }])
+        );
+    }
+
+    #[test]
+    fn half_renames_are_a_removal_and_a_creation() {
+        assert_eq!(
+            FileChange::from_event(&event(EventKind::Modify(ModifyKind::Name(RenameMode::From)), &["/d/a"])),
+            Some(vec![FileChange::Removed("/d/a".into())])
+        );
+        assert_eq!(
+            FileChange::from_event(&event(EventKind::Modify(ModifyKind::Name(RenameMode::To)), &["/d/b"])),
+            Some(vec![FileChange::Created("/d/b".into())])
+        );
+    }
+
+    #[test]
+    fn vague_events_ask_for_a_rescan() {
+        assert_eq!(FileChange::from_event(&event(EventKind::Any, &["/d/a"])), None);
+        assert_eq!(FileChange::from_event(&event(EventKind::Other, &[])), None);
+
+        let overflow = Event::new(EventKind::Other).set_flag(Flag::Rescan);
+        assert_eq!(FileChange::from_event(&overflow), None);
+        let flagged_create = event(EventKind::Create(CreateKind::Any), &["/d/a"]).set_flag(Flag::Rescan);
+        assert_eq!(FileChange::from_event(&flagged_create), None);
+    }
+}
@@ -19,7 +19,7 @@ async fn async_main() -> Result<(), eframe::Error> {
     env_logger::init();
     info!("Starting Caesar commander");
 
-    let params = Cli::new();
+    let params = Cli::params();
 
     let options = NativeOptions {
         viewport: ViewportBuilder::default()
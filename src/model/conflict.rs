@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TransferKind {
+    Copy,
+    Move,
+}
+
+/// What to do when the destination of a copy or move already exists.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConflictPolicy {
+    Ask,
+    /// Replaces the destination; a folder landing on a folder is merged
+    /// into it, each clashing entry replaced in turn.
+    Overwrite,
+    Skip,
+    RenameWithSuffix,
+    /// Like `Overwrite`, but only files newer than the ones they replace.
+    OverwriteIfNewer,
+}
+
+/// How a copy or move treats symbolic links.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum LinkPolicy {
+    /// Links are recreated as links pointing at the same target.
+    #[default]
+    Preserve,
+    /// Links are replaced by copies of what they point at.
+    Dereference,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TransferOutcome {
+    /// Done, with the path the entry ended up at.
+    Transferred(PathBuf),
+    Skipped,
+    /// The destination exists and the policy was `Ask`; nothing was touched.
+    Conflict,
+}
+
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub kind: TransferKind,
+    pub src: PathBuf,
+    pub dst: PathBuf,
+}
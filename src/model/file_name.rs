@@ -0,0 +1,49 @@
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+/// Shows a file name as text. Bytes that are not valid UTF-8 become `\xNN`
+/// and backslashes are doubled, so distinct names stay distinct on screen
+/// and `unescape` gives back the exact name.
+pub fn escape(name: &OsStr) -> String {
+    let mut out = String::new();
+    for chunk in name.as_bytes().utf8_chunks() {
+        out.push_str(&chunk.valid().replace('\\', "\\\\"));
+        for byte in chunk.invalid() {
+            out.push_str(&format!("\\x{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Turns text produced by `escape` (possibly edited) back into raw bytes.
+/// `\\` is a backslash, and only `\xNN` with NN of 80 or above is read as a
+/// byte, as `escape` never produces anything lower; case transforms may turn
+/// it into `\XNN`. Any other backslash, as typed by the user, stays as it is.
+pub fn unescape(text: &str) -> OsString {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i..i + 4)
+            .filter(|b| b.starts_with(b"\\x") || b.starts_with(b"\\X"))
+            .and_then(|b| std::str::from_utf8(&b[2..]).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .filter(|byte| *byte >= 0x80);
+
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None if bytes[i..].starts_with(b"\\\\") => {
+                out.push(b'\\');
+                i += 2;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    OsString::from_vec(out)
+}
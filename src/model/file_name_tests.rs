@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use crate::model::file_name::{escape, unescape};
+
+    #[test]
+    fn valid_names_are_unchanged() {
+        for name in ["plain.txt", "ünïcødé 名前"] {
+            assert_eq!(escape(OsStr::new(name)), name);
+            assert_eq!(unescape(name), OsStr::new(name));
+        }
+    }
+
+    #[test]
+    fn backslashes_are_doubled_so_escapes_stay_unambiguous() {
+        let literal = OsStr::new("foo\\xE9");
+        let raw = OsStr::from_bytes(b"foo\xe9");
+
+        assert_eq!(escape(literal), "foo\\\\xE9");
+        assert_ne!(escape(literal), escape(raw));
+        for name in [literal, raw, OsStr::new("back\\slash"), OsStr::new("a\\x41\\"), OsStr::from_bytes(b"\\\xff")] {
+            assert_eq!(unescape(&escape(name)), name);
+        }
+        // typed by hand
+        assert_eq!(unescape("back\\slash"), OsStr::new("back\\slash"));
+    }
+
+    #[test]
+    fn invalid_bytes_round_trip() {
+        let raw = OsStr::from_bytes(b"caf\xe9 \xff\xfe.txt");
+
+        let shown = escape(raw);
+
+        assert_eq!(shown, "caf\\xE9 \\xFF\\xFE.txt");
+        assert_eq!(unescape(&shown), raw);
+        assert_eq!(unescape(&shown.to_lowercase()), OsStr::from_bytes(b"caf\xe9 \xff\xfe.txt"));
+        assert_eq!(unescape("CAF\\XE9"), OsStr::from_bytes(b"CAF\xe9"));
+    }
+}
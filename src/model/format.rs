@@ -0,0 +1,171 @@
+use chrono::format::{Item as FormatItem, StrftimeItems};
+use chrono::{DateTime, Local};
+
+/// How the Size column shows a byte count.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum SizeFormat {
+    /// `1234567`
+    Bytes,
+    /// `1,234,567`
+    Thousands,
+    /// `1.2 MB`, in powers of 1000
+    Si,
+    /// `1.2 MiB`, in powers of 1024
+    #[default]
+    Iec,
+}
+
+impl SizeFormat {
+    pub const ALL: [SizeFormat; 4] = [SizeFormat::Bytes, SizeFormat::Thousands, SizeFormat::Si, SizeFormat::Iec];
+
+    pub fn format(self, bytes: u64) -> String {
+        match self {
+            SizeFormat::Bytes => bytes.to_string(),
+            SizeFormat::Thousands => thousands(bytes),
+            SizeFormat::Si => scaled(bytes, 1000.0, &["B", "kB", "MB", "GB", "TB", "PB", "EB"]),
+            SizeFormat::Iec => scaled(bytes, 1024.0, &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+        }
+    }
+
+    /// Name used in the configuration file.
+    pub fn name(self) -> &'static str {
+        match self {
+            SizeFormat::Bytes => "bytes",
+            SizeFormat::Thousands => "thousands",
+            SizeFormat::Si => "si",
+            SizeFormat::Iec => "iec",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SizeFormat> {
+        SizeFormat::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SizeFormat::Bytes => "Bytes",
+            SizeFormat::Thousands => "Bytes with separators",
+            SizeFormat::Si => "Decimal units (kB, MB)",
+            SizeFormat::Iec => "Binary units (KiB, MiB)",
+        }
+    }
+}
+
+fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+fn scaled(bytes: u64, base: f64, units: &[&str]) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit + 1 < units.len() {
+        value /= base;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", value, units[unit])
+    }
+}
+
+/// How dates are shown in the Modified and Deleted columns.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum DateFormat {
+    /// `2024-01-31 13:05`
+    #[default]
+    Short,
+    /// `2024-01-31T13:05:09+01:00`
+    Iso,
+    /// `Wed, 31 Jan 2024 13:05:09 +0100`
+    Rfc2822,
+    /// `3 min ago`, falling back to `Short` after a month
+    Relative,
+    /// A strftime pattern, checked by `DateFormat::custom`.
+    Custom(String),
+}
+
+impl DateFormat {
+    pub const PRESETS: [DateFormat; 4] = [DateFormat::Short, DateFormat::Iso, DateFormat::Rfc2822, DateFormat::Relative];
+
+    const SHORT: &'static str = "%Y-%m-%d %H:%M";
+
+    /// A custom pattern, or `None` if chrono cannot format it.
+    pub fn custom(pattern: &str) -> Option<DateFormat> {
+        let valid = !pattern.is_empty()
+            && StrftimeItems::new(pattern).all(|item| !matches!(item, FormatItem::Error));
+        valid.then(|| DateFormat::Custom(pattern.to_string()))
+    }
+
+    /// Formats `date` as seen at `now`, which only matters for `Relative`.
+    pub fn format(&self, date: &DateTime<Local>, now: &DateTime<Local>) -> String {
+        match self {
+            DateFormat::Short => date.format(Self::SHORT).to_string(),
+            DateFormat::Iso => date.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            DateFormat::Rfc2822 => date.to_rfc2822(),
+            DateFormat::Relative => Self::relative(date, now),
+            DateFormat::Custom(pattern) => date.format(pattern).to_string(),
+        }
+    }
+
+    fn relative(date: &DateTime<Local>, now: &DateTime<Local>) -> String {
+        let elapsed = now.signed_duration_since(date);
+        let plural = |n: i64, unit: &str| format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" });
+
+        if elapsed.num_seconds() < 0 || elapsed.num_days() >= 30 {
+            date.format(Self::SHORT).to_string()
+        } else if elapsed.num_minutes() < 1 {
+            "just now".to_string()
+        } else if elapsed.num_hours() < 1 {
+            format!("{} min ago", elapsed.num_minutes())
+        } else if elapsed.num_days() < 1 {
+            plural(elapsed.num_hours(), "hour")
+        } else {
+            plural(elapsed.num_days(), "day")
+        }
+    }
+
+    /// Name used in the configuration file; custom formats are stored as
+    /// their pattern.
+    pub fn name(&self) -> &str {
+        match self {
+            DateFormat::Short => "short",
+            DateFormat::Iso => "iso",
+            DateFormat::Rfc2822 => "rfc2822",
+            DateFormat::Relative => "relative",
+            DateFormat::Custom(pattern) => pattern,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DateFormat> {
+        DateFormat::PRESETS.into_iter()
+            .find(|format| format.name() == name)
+            .or_else(|| DateFormat::custom(name))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            DateFormat::Short => "Short".to_string(),
+            DateFormat::Iso => "ISO 8601".to_string(),
+            DateFormat::Rfc2822 => "RFC 2822".to_string(),
+            DateFormat::Relative => "Relative".to_string(),
+            DateFormat::Custom(pattern) => format!("Custom ({})", pattern),
+        }
+    }
+}
+
+/// The user's choice of size and date formats.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Formats {
+    pub size: SizeFormat,
+    pub date: DateFormat,
+}
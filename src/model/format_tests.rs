@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local, TimeZone};
+    use crate::model::format::{DateFormat, SizeFormat};
+
+    #[test]
+    fn sizes_in_each_format() {
+        assert_eq!(SizeFormat::Bytes.format(1234567), "1234567");
+        assert_eq!(SizeFormat::Thousands.format(1234567), "1,234,567");
+        assert_eq!(SizeFormat::Thousands.format(123), "123");
+        assert_eq!(SizeFormat::Thousands.format(123456), "123,456");
+        assert_eq!(SizeFormat::Si.format(999), "999 B");
+        assert_eq!(SizeFormat::Si.format(1234567), "1.2 MB");
+        assert_eq!(SizeFormat::Iec.format(1023), "1023 B");
+        assert_eq!(SizeFormat::Iec.format(1536), "1.5 KiB");
+        assert_eq!(SizeFormat::Iec.format(u64::MAX), "16.0 EiB");
+    }
+
+    #[test]
+    fn dates_in_presets_and_custom_patterns() {
+        let date = Local.with_ymd_and_hms(2024, 1, 31, 13, 5, 9).unwrap();
+
+        assert_eq!(DateFormat::Short.format(&date, &date), "2024-01-31 13:05");
+        assert!(DateFormat::Iso.format(&date, &date).starts_with("2024-01-31T13:05:09"));
+        assert_eq!(DateFormat::custom("%d.%m.%Y").unwrap().format(&date, &date), "31.01.2024");
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert_eq!(DateFormat::custom("%Q"), None);
+        assert_eq!(DateFormat::custom(""), None);
+        assert_eq!(DateFormat::from_name("%"), None);
+    }
+
+    #[test]
+    fn relative_times() {
+        let now = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let ago = |d: Duration| DateFormat::Relative.format(&(now - d), &now);
+
+        assert_eq!(ago(Duration::seconds(20)), "just now");
+        assert_eq!(ago(Duration::minutes(3)), "3 min ago");
+        assert_eq!(ago(Duration::hours(1)), "1 hour ago");
+        assert_eq!(ago(Duration::hours(5)), "5 hours ago");
+        assert_eq!(ago(Duration::days(2)), "2 days ago");
+        assert_eq!(ago(Duration::days(40)), "2024-01-21 12:00");
+        assert_eq!(ago(Duration::minutes(-5)), "2024-03-01 12:05");
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for format in SizeFormat::ALL {
+            assert_eq!(SizeFormat::from_name(format.name()), Some(format));
+        }
+        for format in DateFormat::PRESETS {
+            assert_eq!(DateFormat::from_name(format.name()), Some(format.clone()));
+        }
+        assert_eq!(DateFormat::from_name("%H:%M"), Some(DateFormat::Custom("%H:%M".into())));
+    }
+}
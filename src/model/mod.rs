@@ -1,27 +1,103 @@
+pub mod conflict;
+pub mod file_name;
+pub mod format;
 pub mod pane_controls;
 pub mod params;
+pub mod sort;
+pub mod totals;
+mod file_name_tests;
+mod format_tests;
+mod sort_tests;
+mod totals_tests;
 
+use std::ffi::OsString;
 use std::path::PathBuf;
 use chrono::{DateTime, Local};
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug)]
 pub enum ItemType {
     File,
     Directory,
+    Symlink {
+        /// As stored in the link, possibly relative to its directory.
+        target: PathBuf,
+        /// The target does not exist.
+        broken: bool,
+        /// The target is a directory, as found when listing.
+        to_dir: bool,
+    },
+    Socket,
+    Fifo,
+    BlockDevice,
+    CharDevice,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Item {
-    pub name: String,
+    /// Raw name as stored on disk; use `display_name` for showing it.
+    pub name: OsString,
     pub path: PathBuf,
     pub item_type: ItemType,
-    pub size: u64,
-    pub modified: DateTime<Local>,
+    /// `None` when the entry's metadata could not be read.
+    pub size: Option<u64>,
+    pub modified: Option<DateTime<Local>>,
+    /// Set for entries listed from the trash.
+    pub trash_info: Option<TrashInfo>,
+    /// Metadata of the entry itself, not following links; `None` when it
+    /// could not be read.
+    pub unix: Option<UnixMeta>,
 }
 
-pub struct Column {
-    pub name: String,
-    pub width: f32,
+impl Item {
+    /// The synthetic ".." row leading to `parent`. No real entry can have
+    /// that name, so it is told apart by name alone.
+    pub fn parent_link(parent: PathBuf) -> Item {
+        Item {
+            name: "..".into(),
+            path: parent,
+            item_type: ItemType::Directory,
+            size: None,
+            modified: None,
+            trash_info: None,
+            unix: None,
+        }
+    }
+
+    pub fn is_parent_link(&self) -> bool {
+        self.name == ".."
+    }
+
+    pub fn display_name(&self) -> String {
+        file_name::escape(&self.name)
+    }
+
+    /// Directories and links that resolve to one.
+    pub fn opens_as_directory(&self) -> bool {
+        match &self.item_type {
+            ItemType::Directory => true,
+            ItemType::Symlink { to_dir, .. } => *to_dir,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrashInfo {
+    pub original_path: PathBuf,
+    pub deleted: Option<DateTime<Local>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnixMeta {
+    /// Permission bits, including setuid, setgid and sticky.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub inode: u64,
+    pub links: u64,
+    pub accessed: Option<DateTime<Local>>,
+    /// Not every file system records it.
+    pub created: Option<DateTime<Local>>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -1,3 +1,15 @@
+use crate::model::conflict::LinkPolicy;
+
 pub enum PaneControlsEvent {
-    MoveSelected
+    CopySelected(LinkPolicy),
+    MoveSelected(LinkPolicy),
+    MakeDirectory,
+    MultiRename,
+    DeleteSelected,
+    DeleteSelectedPermanently,
+    OpenTrash,
+    RestoreSelected,
+    EmptyTrash,
+    Undo,
+    Redo,
 }
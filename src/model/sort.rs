@@ -0,0 +1,180 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use crate::model::Item;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SortKey {
+    Name,
+    Extension,
+    Size,
+    Modified,
+}
+
+/// How a pane orders its items. Names compare naturally, so `file2` comes
+/// before `file10`; entries that compare equal fall back to the name.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+    pub case_sensitive: bool,
+    /// Directories stay on top in either direction.
+    pub dirs_first: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder { key: SortKey::Name, descending: false, case_sensitive: false, dirs_first: true }
+    }
+}
+
+impl SortOrder {
+    /// Sorting by the current key again flips the direction; a new key
+    /// starts ascending.
+    pub fn toggled(self, key: SortKey) -> SortOrder {
+        SortOrder {
+            key,
+            descending: key == self.key && !self.descending,
+            ..self
+        }
+    }
+
+    pub fn sort(&self, items: &mut Vec<Item>) {
+        // names are escaped and folded once per item rather than on every
+        // comparison
+        let mut keyed: Vec<(SortKeys, Item)> = items.drain(..).map(|item| (self.keys(&item), item)).collect();
+        keyed.sort_by(|a, b| self.compare(a, b));
+        items.extend(keyed.into_iter().map(|(_, item)| item));
+    }
+
+    /// Merges `sorted`, already in this order, into the sorted `items`
+    /// without sorting everything again.
+    pub fn merge(&self, items: &mut Vec<Item>, sorted: Vec<Item>) {
+        let keyed = |items: Vec<Item>| items.into_iter().map(|item| (self.keys(&item), item)).peekable();
+        let mut old = keyed(std::mem::take(items));
+        let mut new = keyed(sorted);
+
+        items.reserve(old.len() + new.len());
+        loop {
+            let next = match (old.peek(), new.peek()) {
+                // an equal new entry goes after the old one, like a stable sort
+                (Some(a), Some(b)) if self.compare(b, a) == Ordering::Less => new.next(),
+                (Some(_), _) => old.next(),
+                (None, _) => new.next(),
+            };
+            let Some((_, item)) = next else { break };
+            items.push(item);
+        }
+    }
+
+    fn keys(&self, item: &Item) -> SortKeys {
+        let name = item.display_name();
+        SortKeys {
+            parent: item.is_parent_link(),
+            dir: self.dirs_first && item.opens_as_directory(),
+            folded: (!self.case_sensitive).then(|| name.to_lowercase()),
+            name,
+            extension: match self.key {
+                SortKey::Extension => Path::new(&item.name).extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+                    .unwrap_or_default(),
+                _ => String::new(),
+            },
+        }
+    }
+
+    fn compare(&self, (keys_a, a): &(SortKeys, Item), (keys_b, b): &(SortKeys, Item)) -> Ordering {
+        // ".." stays on top in either direction
+        let parent = keys_b.parent.cmp(&keys_a.parent);
+        if parent != Ordering::Equal {
+            return parent;
+        }
+        let grouped = keys_b.dir.cmp(&keys_a.dir);
+        if grouped != Ordering::Equal {
+            return grouped;
+        }
+
+        let by_name = || {
+            natural_cmp_folded(&keys_a.name, keys_a.folded.as_deref(), &keys_b.name, keys_b.folded.as_deref())
+                .then_with(|| a.name.cmp(&b.name))
+        };
+        let ordering = match self.key {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Extension => keys_a.extension.cmp(&keys_b.extension),
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+        }
+        .then_with(by_name);
+
+        if self.descending { ordering.reverse() } else { ordering }
+    }
+}
+
+/// What an item is ordered by, worked out once per item.
+struct SortKeys {
+    parent: bool,
+    /// Only set when directories go first.
+    dir: bool,
+    name: String,
+    /// `name` lowercased, when case is ignored.
+    folded: Option<String>,
+    /// Lowercased, and only filled in when sorting by extension.
+    extension: String,
+}
+
+/// Compares runs of digits by their value and everything else by character.
+/// Without `case_sensitive`, names differing only in case are ordered by
+/// case as a last resort, to keep the order stable.
+#[cfg(test)]
+pub fn natural_cmp(a: &str, b: &str, case_sensitive: bool) -> Ordering {
+    let fold = |name: &str| (!case_sensitive).then(|| name.to_lowercase());
+    natural_cmp_folded(a, fold(a).as_deref(), b, fold(b).as_deref())
+}
+
+/// `natural_cmp` of names lowercased in advance, or `None` when case counts.
+fn natural_cmp_folded(a: &str, folded_a: Option<&str>, b: &str, folded_b: Option<&str>) -> Ordering {
+    match (folded_a, folded_b) {
+        (Some(folded_a), Some(folded_b)) => natural_cmp_exact(folded_a, folded_b),
+        _ => Ordering::Equal,
+    }
+    .then_with(|| natural_cmp_exact(a, b))
+}
+
+fn natural_cmp_exact(a: &str, b: &str) -> Ordering {
+    // UTF-8 bytes order like the characters they encode, and digits never
+    // occur inside a multi-byte character, so there is no need to decode
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    loop {
+        let (Some(&ca), Some(&cb)) = (a.first(), b.first()) else {
+            return a.len().cmp(&b.len());
+        };
+
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let (run_a, rest_a) = split_digits(a);
+            let (run_b, rest_b) = split_digits(b);
+            let (value_a, value_b) = (trim_zeros(run_a), trim_zeros(run_b));
+
+            let ordering = value_a.len().cmp(&value_b.len())
+                .then_with(|| value_a.cmp(value_b))
+                .then_with(|| run_a.len().cmp(&run_b.len()));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else {
+            if ca != cb {
+                return ca.cmp(&cb);
+            }
+            (a, b) = (&a[1..], &b[1..]);
+        }
+    }
+}
+
+fn split_digits(s: &[u8]) -> (&[u8], &[u8]) {
+    let end = s.iter().position(|c| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+    let start = digits.iter().position(|&c| c != b'0').unwrap_or(digits.len());
+    &digits[start..]
+}
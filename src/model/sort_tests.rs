@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::path::Path;
+    use chrono::{Local, TimeZone};
+    use crate::model::sort::{natural_cmp, SortKey, SortOrder};
+    use crate::model::{Item, ItemType};
+
+    fn item(name: &str, item_type: ItemType, size: u64, day: u32) -> Item {
+        Item {
+            name: name.into(),
+            path: Path::new("/d").join(name),
+            item_type,
+            size: Some(size),
+            modified: Some(Local.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()),
+            trash_info: None,
+            unix: None,
+        }
+    }
+
+    fn names(items: &[Item]) -> Vec<String> {
+        items.iter().map(Item::display_name).collect()
+    }
+
+    fn sample() -> Vec<Item> {
+        vec![
+            item("file10.txt", ItemType::File, 30, 3),
+            item("File2.log", ItemType::File, 10, 1),
+            item("zdir", ItemType::Directory, 0, 9),
+            item("file1.txt", ItemType::File, 20, 2),
+            item("adir", ItemType::Directory, 0, 8),
+        ]
+    }
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        assert_eq!(natural_cmp("file2", "file10", true), Ordering::Less);
+        assert_eq!(natural_cmp("a01", "a1", true), Ordering::Greater);
+        assert_eq!(natural_cmp("a1b2", "a1b10", true), Ordering::Less);
+        assert_eq!(natural_cmp("abc", "ab", true), Ordering::Greater);
+        assert_eq!(natural_cmp("99999999999999999999999", "100000000000000000000000", true), Ordering::Less);
+    }
+
+    #[test]
+    fn non_ascii_names_order_by_character() {
+        assert_eq!(natural_cmp("é2", "é10", true), Ordering::Less);
+        assert_eq!(natural_cmp("z", "é", true), Ordering::Less);
+        assert_eq!(natural_cmp("é", "\u{1F600}", true), Ordering::Less);
+        assert_eq!(natural_cmp("Éa", "éb", false), Ordering::Less);
+    }
+
+    #[test]
+    fn case_insensitive_option() {
+        assert_eq!(natural_cmp("B", "a", true), Ordering::Less);
+        assert_eq!(natural_cmp("B", "a", false), Ordering::Greater);
+        assert_eq!(natural_cmp("a", "A", false), natural_cmp("a", "A", true));
+    }
+
+    #[test]
+    fn sorts_by_name_with_directories_first() {
+        let mut items = sample();
+        SortOrder::default().sort(&mut items);
+
+        assert_eq!(names(&items), ["adir", "zdir", "file1.txt", "File2.log", "file10.txt"]);
+    }
+
+    #[test]
+    fn descending_keeps_directories_on_top() {
+        let mut items = sample();
+        SortOrder::default().toggled(SortKey::Name).sort(&mut items);
+
+        assert_eq!(names(&items), ["zdir", "adir", "file10.txt", "File2.log", "file1.txt"]);
+    }
+
+    #[test]
+    fn sorts_by_extension_size_and_date() {
+        let order = SortOrder { dirs_first: false, ..Default::default() };
+
+        let mut items = sample();
+        order.toggled(SortKey::Extension).sort(&mut items);
+        assert_eq!(names(&items), ["adir", "zdir", "File2.log", "file1.txt", "file10.txt"]);
+
+        order.toggled(SortKey::Size).sort(&mut items);
+        assert_eq!(names(&items), ["adir", "zdir", "File2.log", "file1.txt", "file10.txt"]);
+
+        order.toggled(SortKey::Modified).sort(&mut items);
+        assert_eq!(names(&items), ["File2.log", "file1.txt", "file10.txt", "adir", "zdir"]);
+    }
+
+    #[test]
+    fn toggling_flips_direction_only_for_the_same_key() {
+        let order = SortOrder::default();
+
+        assert!(order.toggled(SortKey::Name).descending);
+        assert!(!order.toggled(SortKey::Name).toggled(SortKey::Name).descending);
+        assert!(!order.toggled(SortKey::Name).toggled(SortKey::Size).descending);
+    }
+
+    #[test]
+    fn parent_link_stays_first() {
+        let mut items = sample();
+        items.push(Item::parent_link("/".into()));
+
+        let order = SortOrder { dirs_first: false, ..Default::default() };
+        order.toggled(SortKey::Name).sort(&mut items);
+        assert_eq!(items[0].display_name(), "..");
+
+        order.toggled(SortKey::Size).toggled(SortKey::Size).sort(&mut items);
+        assert_eq!(items[0].display_name(), "..");
+    }
+
+    #[test]
+    fn merging_a_sorted_batch_matches_sorting_everything() {
+        let order = SortOrder::default();
+        let mut all = sample();
+        all.push(Item::parent_link("/".into()));
+        all.push(item("file3.txt", ItemType::File, 5, 4));
+        let mut expected = all.clone();
+        order.sort(&mut expected);
+
+        let mut batch = all.split_off(3);
+        order.sort(&mut all);
+        order.sort(&mut batch);
+        order.merge(&mut all, batch);
+
+        assert_eq!(names(&all), names(&expected));
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::BTreeSet;
+use crate::model::{Item, ItemType};
+
+/// What a listing holds and how much of it is selected. The ".." row does
+/// not count.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Totals {
+    pub files: usize,
+    pub selected_files: usize,
+    pub dirs: usize,
+    pub selected_dirs: usize,
+    /// Of files, and of directories `size_of` knows.
+    pub bytes: u64,
+    pub selected_bytes: u64,
+}
+
+impl Totals {
+    /// Totals of `items`, where `selected` holds indices into them. Only
+    /// directories get sizes from `size_of`; other items use their own.
+    pub fn of(items: &[Item], selected: &BTreeSet<usize>, size_of: impl Fn(&Item) -> Option<u64>) -> Self {
+        let mut totals = Totals::default();
+        let mut add = |item: &Item, selected: bool| {
+            let (count, selected_count, size) = match item.item_type {
+                ItemType::Directory => (&mut totals.dirs, &mut totals.selected_dirs, size_of(item)),
+                _ => (&mut totals.files, &mut totals.selected_files, item.size),
+            };
+            let size = size.unwrap_or(0);
+            *count += 1;
+            totals.bytes += size;
+            if selected {
+                *selected_count += 1;
+                totals.selected_bytes += size;
+            }
+        };
+
+        let mut selected = selected.iter().peekable();
+        for (i, item) in items.iter().enumerate() {
+            let is_selected = selected.next_if_eq(&&i).is_some();
+            if !item.is_parent_link() {
+                add(item, is_selected);
+            }
+        }
+        totals
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::path::Path;
+    use crate::model::totals::Totals;
+    use crate::model::{Item, ItemType};
+
+    fn item(name: &str, item_type: ItemType, size: u64) -> Item {
+        Item {
+            name: name.into(),
+            path: Path::new("/d").join(name),
+            item_type,
+            size: Some(size),
+            modified: None,
+            trash_info: None,
+            unix: None,
+        }
+    }
+
+    #[test]
+    fn counts_files_and_directories_apart() {
+        let items = vec![
+            Item::parent_link("/".into()),
+            item("measured", ItemType::Directory, 4096),
+            item("unmeasured", ItemType::Directory, 4096),
+            item("a", ItemType::File, 100),
+            item("b", ItemType::File, 20),
+            item("c", ItemType::File, 3),
+        ];
+        let selected = BTreeSet::from([0, 1, 3, 5]);
+
+        let totals = Totals::of(&items, &selected, |item| (item.name == "measured").then_some(1000));
+        assert_eq!(totals, Totals {
+            files: 3,
+            selected_files: 2,
+            dirs: 2,
+            selected_dirs: 1,
+            bytes: 1123,
+            selected_bytes: 1103,
+        });
+    }
+
+    #[test]
+    fn stale_indices_are_ignored() {
+        let items = vec![item("a", ItemType::File, 5)];
+
+        let totals = Totals::of(&items, &BTreeSet::from([0, 7]), |_| None);
+        assert_eq!((totals.selected_files, totals.selected_bytes), (1, 5));
+    }
+}
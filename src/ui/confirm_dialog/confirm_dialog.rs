@@ -0,0 +1,28 @@
+use egui::*;
+
+/// Yes/no modal. Returns `Some(answer)` once the user has decided; Escape
+/// and clicking outside count as "no".
+pub fn show(ctx: &Context, id: &str, title: &str, message: &str) -> Option<bool> {
+    let mut answer = None;
+
+    let modal = Modal::new(Id::new(id)).show(ctx, |ui| {
+        ui.heading(title);
+        ui.separator();
+        ui.label(message);
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("Yes").clicked() || ui.input(|i| i.key_pressed(Key::Enter)) {
+                answer = Some(true);
+            }
+            if ui.button("No").clicked() {
+                answer = Some(false);
+            }
+        });
+    });
+
+    if answer.is_none() && modal.should_close() {
+        answer = Some(false);
+    }
+    answer
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::Path;
+use chrono::{DateTime, Local};
+use egui::*;
+use crate::model::conflict::{Conflict, ConflictPolicy, TransferKind};
+
+pub enum ConflictResolution {
+    Resolve {
+        policy: ConflictPolicy,
+        apply_to_all: bool,
+    },
+    Cancel,
+}
+
+#[derive(Default)]
+pub struct ConflictDialog {
+    apply_to_all: bool,
+}
+
+impl ConflictDialog {
+    pub fn ui(&mut self, ctx: &Context, conflict: &Conflict) -> Option<ConflictResolution> {
+        let mut resolution = None;
+
+        let modal = Modal::new(Id::new("conflict_dialog")).show(ctx, |ui| {
+            let action = match conflict.kind {
+                TransferKind::Copy => "Copy",
+                TransferKind::Move => "Move",
+            };
+            ui.heading(format!("{}: target already exists", action));
+            ui.separator();
+
+            Grid::new("conflict_dialog_details").num_columns(2).show(ui, |ui| {
+                ui.label("Source");
+                ui.label(Self::describe(&conflict.src));
+                ui.end_row();
+                ui.label("Target");
+                ui.label(Self::describe(&conflict.dst));
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.checkbox(&mut self.apply_to_all, "Apply to all");
+
+            ui.horizontal(|ui| {
+                let choices = [
+                    ("Overwrite", ConflictPolicy::Overwrite),
+                    ("Overwrite if newer", ConflictPolicy::OverwriteIfNewer),
+                    ("Skip", ConflictPolicy::Skip),
+                    ("Rename", ConflictPolicy::RenameWithSuffix),
+                ];
+                for (label, policy) in choices {
+                    if ui.button(label).clicked() {
+                        resolution = Some(ConflictResolution::Resolve {
+                            policy,
+                            apply_to_all: self.apply_to_all,
+                        });
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    resolution = Some(ConflictResolution::Cancel);
+                }
+            });
+        });
+
+        if resolution.is_none() && modal.should_close() {
+            resolution = Some(ConflictResolution::Cancel);
+        }
+        if resolution.is_some() {
+            self.apply_to_all = false;
+        }
+        resolution
+    }
+
+    fn describe(path: &Path) -> String {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) => {
+                let modified = metadata.modified()
+                    .map(|t| DateTime::<Local>::from(t).to_rfc2822())
+                    .unwrap_or_default();
+                format!("{}\n{} bytes, {}", path.display(), metadata.len(), modified)
+            }
+            Err(_) => path.display().to_string(),
+        }
+    }
+}
@@ -1,7 +1,13 @@
+use crate::file_system::config::Config;
 use crate::file_system::navigator::Navigator;
+use crate::file_system::journal::Journal;
+use crate::file_system::operations::OperationManager;
+use crate::file_system::trash::Trash;
+use crate::ui::conflict_dialog::conflict_dialog::ConflictDialog;
 use crate::ui::double_pane::double_pane_view::DoublePaneView;
 use crate::ui::pane_controls::controls::PaneControls;
 use std::path::PathBuf;
+use crate::ui::file_pane::columns::ColumnLayout;
 use crate::ui::file_pane::container::Container;
 
 pub struct DoublePane {
@@ -10,8 +16,13 @@ pub struct DoublePane {
 
 impl DoublePane {
     pub fn new(left: PathBuf, right: PathBuf) -> Self {
-        let left_pane = Container::new(Navigator::new(&left));
-        let right_pane = Container::new(Navigator::new(&right));
+        let config = Config::open();
+        let mut left_pane = Container::new(Navigator::new(&left));
+        let mut right_pane = Container::new(Navigator::new(&right));
+        left_pane.view.set_formats(config.formats.clone());
+        right_pane.view.set_formats(config.formats.clone());
+        left_pane.set_layout(ColumnLayout::from_config(&config.left));
+        right_pane.set_layout(ColumnLayout::from_config(&config.right));
 
         let pane_controls = PaneControls::new();
 
@@ -21,6 +32,11 @@ impl DoublePane {
                 right_file_pane: right_pane,
                 focus_state: FocusState::LeftPane,
                 pane_controls,
+                conflict_dialog: ConflictDialog::default(),
+                operations: OperationManager::new(),
+                journal: Journal::open(Trash::new()),
+                notice: None,
+                config,
             }
         }
     }
@@ -1,6 +1,19 @@
 use egui::*;
+use std::time::Duration;
+use log::info;
+use crate::file_system::config::Config;
+use crate::file_system::journal::Journal;
+use crate::file_system::operations::{JobState, OperationManager};
+use crate::model::format::Formats;
+use crate::model::pane_controls::PaneControlsEvent;
+use crate::ui::conflict_dialog::conflict_dialog::ConflictDialog;
+use crate::ui::confirm_dialog::confirm_dialog;
+use crate::ui::multi_rename::multi_rename_dialog::MultiRenameResult;
+use crate::ui::prompt_dialog::prompt_dialog::{self, PromptResult};
 use crate::ui::double_pane::double_pane::FocusState;
 use crate::ui::file_pane;
+use crate::ui::file_pane::columns::ColumnLayout;
+use crate::ui::file_pane::view::NavigatedEvent;
 use crate::ui::pane_controls::controls::PaneControls;
 
 pub struct DoublePaneView {
@@ -8,65 +21,301 @@ pub struct DoublePaneView {
     pub right_file_pane: file_pane::Container,
     pub focus_state: FocusState,
     pub pane_controls: PaneControls,
+    pub conflict_dialog: ConflictDialog,
+    pub operations: OperationManager,
+    pub journal: Journal,
+    /// Last undo/redo error, shown until dismissed.
+    pub notice: Option<String>,
+    pub config: Config,
 }
 
 
 impl DoublePaneView {
     pub fn ui(&mut self, ui: &mut Ui) {
-        self.handle_navigation_event();
+        self.handle_navigation_event(ui.ctx());
         self.handle_pane_controls_event();
+        self.handle_operations(ui.ctx());
 
+        let modal_open = self.modal_open();
+        let typing = modal_open
+            || self.left_file_pane.view.is_renaming()
+            || self.right_file_pane.view.is_renaming()
+            || self.left_file_pane.view.is_editing_path()
+            || self.right_file_pane.view.is_editing_path();
         const CONTROLS_HEIGHT: f32 = 80.0;
-        let pane_height = ui.available_height() - CONTROLS_HEIGHT;
+        const JOB_ROW_HEIGHT: f32 = 28.0;
+        let rows = self.operations.jobs().len() + self.notice.is_some() as usize;
+        let progress_height = rows as f32 * JOB_ROW_HEIGHT;
+        let pane_height = ui.available_height() - CONTROLS_HEIGHT - progress_height;
 
         ui.horizontal(|ui| {
             ui.with_layout(Layout::left_to_right(Align::Center).with_main_justify(false), |ui| {
                 ui.allocate_ui(Vec2::new(ui.available_width() / 2.0, pane_height), |ui| {
-                    self.left_file_pane.view.ui(ui, self.focus_state.is_left());
+                    self.left_file_pane.view.ui(ui, self.focus_state.is_left() && !modal_open);
                 });
             });
 
             ui.with_layout(Layout::left_to_right(Align::Center).with_main_justify(false), |ui| {
                 ui.allocate_ui(Vec2::new(ui.available_width() / 2.0, pane_height), |ui| {
-                    self.right_file_pane.view.ui(ui, self.focus_state.is_right());
+                    self.right_file_pane.view.ui(ui, self.focus_state.is_right() && !modal_open);
                 });
             });
         });
 
         ui.separator();
 
-        self.pane_controls.view.ui(ui);
+        self.progress_panel(ui);
+        self.pane_controls.view.ui(ui, !typing);
 
-        self.handle_focus_switch(ui);
+        if modal_open {
+            self.handle_conflicts(ui.ctx());
+            self.handle_deletion_confirmation(ui.ctx());
+            self.handle_mkdir_prompt(ui.ctx());
+            self.handle_multi_rename(ui.ctx());
+        } else if !typing {
+            self.handle_focus_switch(ui);
+        }
     }
 
-    fn handle_navigation_event(&mut self) {
-        while let Ok(event) = self.left_file_pane.receiver.try_recv() {
-            self.left_file_pane.handle_navigation_event(&event);
+    /// Queues the jobs the panes prepared and refreshes both panes whenever
+    /// one finishes, since a job may touch either side.
+    fn handle_operations(&mut self, ctx: &Context) {
+        for pane in [&mut self.left_file_pane, &mut self.right_file_pane] {
+            for (title, operations) in pane.take_jobs() {
+                self.operations.submit(title, operations);
+            }
         }
 
-        while let Ok(event) = self.right_file_pane.receiver.try_recv() {
-            self.right_file_pane.handle_navigation_event(&event);
+        let finished = self.operations.poll();
+        let recorded = self.operations.take_recorded().into_iter()
+            .chain(self.left_file_pane.take_recorded())
+            .chain(self.right_file_pane.take_recorded());
+        for op in recorded {
+            self.journal.record(op);
+        }
+
+        if !finished.is_empty() {
+            self.left_file_pane.refresh_items();
+            self.right_file_pane.refresh_items();
+        }
+
+        if self.operations.is_busy() {
+            ctx.request_repaint_after(Duration::from_millis(100));
         }
     }
 
-    fn handle_pane_controls_event(&mut self) {
-        while let Ok(event) = self.pane_controls.receiver.try_recv() {
-            match self.focus_state {
-                FocusState::LeftPane => {
-                    self.left_file_pane.handle_pane_controls_event(
-                        &event, &self.right_file_pane.navigator.current_path
-                    );
-                    self.right_file_pane.refresh_items();
+    /// Shows sizes and dates the same way in both panes and remembers it.
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.left_file_pane.view.set_formats(formats.clone());
+        self.right_file_pane.view.set_formats(formats.clone());
+        self.config.formats = formats;
+        self.config.save();
+    }
+
+    /// Remembers the columns a pane was given; each pane keeps its own.
+    fn save_layout(&mut self, left: bool, layout: &ColumnLayout) {
+        let saved = if left { &mut self.config.left } else { &mut self.config.right };
+        *saved = layout.to_config();
+        self.config.save();
+    }
+
+    fn undo_redo(&mut self, redo: bool) {
+        if self.operations.is_busy() {
+            self.notice = Some("Wait for running operations to finish before undoing".into());
+            return;
+        }
+
+        let result = if redo { self.journal.redo() } else { self.journal.undo() };
+        match result {
+            Ok(Some(done)) => {
+                info!("{} {}", if redo { "Redid" } else { "Undid" }, done);
+                self.notice = None;
+            }
+            Ok(None) => {}
+            Err(e) => self.notice = Some(e.to_string()),
+        }
+        self.left_file_pane.refresh_items();
+        self.right_file_pane.refresh_items();
+    }
+
+    fn progress_panel(&mut self, ui: &mut Ui) {
+        if let Some(notice) = &self.notice {
+            let mut dismiss = false;
+            ui.horizontal(|ui| {
+                ui.colored_label(ui.visuals().error_fg_color, notice);
+                dismiss = ui.button("Dismiss").clicked();
+            });
+            if dismiss {
+                self.notice = None;
+            }
+        }
+
+        let mut dismissed = Vec::new();
+        let size = self.config.formats.size;
+
+        for job in self.operations.jobs() {
+            ui.horizontal(|ui| {
+                ui.label(&job.title);
+
+                match &job.state {
+                    JobState::Queued => {
+                        ui.label("queued");
+                    }
+                    JobState::Running | JobState::Done => {
+                        let progress = &job.progress;
+                        let mut text = format!(
+                            "{} of {} files, {} of {}",
+                            progress.files_done,
+                            progress.files_total,
+                            size.format(progress.bytes_done),
+                            size.format(progress.bytes_total),
+                        );
+                        if job.is_paused() {
+                            text.push_str(", paused");
+                        } else if let Some(eta) = job.eta() {
+                            text.push_str(&format!(", {}s left", eta.as_secs()));
+                        }
+                        ui.add(ProgressBar::new(job.fraction()).desired_width(200.0).text(text))
+                            .on_hover_text(progress.current.display().to_string());
+                    }
+                    JobState::Cancelled => {
+                        ui.label("cancelled");
+                    }
+                    JobState::Failed(errors) => {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("{} failed", errors.len()))
+                            .on_hover_text(errors.join("\n"));
+                    }
+                }
+
+                if job.is_active() {
+                    let pause_label = if job.is_paused() { "Resume" } else { "Pause" };
+                    if ui.button(pause_label).clicked() {
+                        self.operations.set_paused(job.id, !job.is_paused());
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.operations.cancel(job.id);
+                    }
+                } else if ui.button("Dismiss").clicked() {
+                    dismissed.push(job.id);
                 }
-                FocusState::RightPane => {
-                    self.right_file_pane.handle_pane_controls_event(
-                        &event, &self.left_file_pane.navigator.current_path
-                    );
-                    self.left_file_pane.refresh_items();
+            });
+        }
+
+        for id in dismissed {
+            self.operations.dismiss(id);
+        }
+    }
+
+    fn modal_open(&self) -> bool {
+        [&self.left_file_pane, &self.right_file_pane].iter().any(|pane| {
+            pane.pending_conflict().is_some()
+                || pane.pending_deletion().is_some()
+                || pane.is_creating_directory()
+                || pane.is_multi_renaming()
+        })
+    }
+
+    fn handle_multi_rename(&mut self, ctx: &Context) {
+        for pane in [&mut self.left_file_pane, &mut self.right_file_pane] {
+            let Some(dialog) = pane.multi_rename_dialog() else { continue };
+
+            match dialog.ui(ctx) {
+                Some(MultiRenameResult::Apply(plan)) => pane.apply_multi_rename(&plan),
+                Some(MultiRenameResult::Cancel) => pane.close_multi_rename(),
+                None => {}
+            }
+        }
+    }
+
+    fn handle_mkdir_prompt(&mut self, ctx: &Context) {
+        for pane in [&mut self.left_file_pane, &mut self.right_file_pane] {
+            let Some(prompt) = pane.mkdir_prompt() else { continue };
+
+            match prompt_dialog::show(ctx, "mkdir_prompt", "New folder", "Name (a/b/c creates nested folders)", prompt) {
+                Some(PromptResult::Submit) => pane.submit_mkdir(),
+                Some(PromptResult::Cancel) => pane.cancel_mkdir(),
+                None => {}
+            }
+        }
+    }
+
+    fn handle_deletion_confirmation(&mut self, ctx: &Context) {
+        for pane in [&mut self.left_file_pane, &mut self.right_file_pane] {
+            let Some(items) = pane.pending_deletion() else { continue };
+
+            let message = match items {
+                [item] => format!("Permanently delete \"{}\"?", item.display_name()),
+                items => format!("Permanently delete {} items?", items.len()),
+            };
+            if let Some(confirmed) = confirm_dialog::show(ctx, "confirm_deletion", "Delete", &message) {
+                pane.confirm_deletion(confirmed);
+            }
+        }
+    }
+
+    fn handle_conflicts(&mut self, ctx: &Context) {
+        let (source, other) = if self.left_file_pane.pending_conflict().is_some() {
+            (&mut self.left_file_pane, &mut self.right_file_pane)
+        } else {
+            (&mut self.right_file_pane, &mut self.left_file_pane)
+        };
+
+        let Some(conflict) = source.pending_conflict() else { return };
+        if let Some(resolution) = self.conflict_dialog.ui(ctx, &conflict) {
+            source.resolve_conflict(resolution);
+            other.refresh_items();
+        }
+    }
+
+    fn handle_navigation_event(&mut self, ctx: &Context) {
+        let mut formats = None;
+        for pane in [&mut self.left_file_pane, &mut self.right_file_pane] {
+            while let Ok(event) = pane.receiver.try_recv() {
+                match event {
+                    NavigatedEvent::FormatsChanged(changed) => formats = Some(changed),
+                    event => pane.handle_navigation_event(event),
                 }
             }
         }
+        if let Some(formats) = formats {
+            self.set_formats(formats);
+        }
+        for left in [true, false] {
+            let pane = if left { &mut self.left_file_pane } else { &mut self.right_file_pane };
+            if pane.take_layout_changed() {
+                let layout = pane.layout().clone();
+                self.save_layout(left, &layout);
+            }
+        }
+
+        // batches and sizes arrive without any input to wake the UI
+        let busy = |pane: &file_pane::Container| pane.is_loading() || pane.is_measuring();
+        if busy(&self.left_file_pane) || busy(&self.right_file_pane) {
+            ctx.request_repaint_after(Duration::from_millis(30));
+        }
+    }
+
+    fn handle_pane_controls_event(&mut self) {
+        while let Ok(event) = self.pane_controls.receiver.try_recv() {
+            if let PaneControlsEvent::Undo | PaneControlsEvent::Redo = event {
+                self.undo_redo(matches!(event, PaneControlsEvent::Redo));
+                continue;
+            }
+
+            let (source, other) = match self.focus_state {
+                FocusState::LeftPane => (&mut self.left_file_pane, &mut self.right_file_pane),
+                FocusState::RightPane => (&mut self.right_file_pane, &mut self.left_file_pane),
+            };
+
+            let into_other = matches!(event, PaneControlsEvent::CopySelected(_) | PaneControlsEvent::MoveSelected(_));
+            if into_other && other.navigator.in_trash() {
+                info!("Not copying or moving into the trash, use F8 to delete");
+                continue;
+            }
+
+            source.handle_pane_controls_event(&event, &other.navigator.current_path);
+            other.refresh_items();
+        }
     }
 
     fn handle_focus_switch(&mut self, ui: &mut Ui) {
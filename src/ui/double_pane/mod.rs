@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod double_pane;
 pub mod double_pane_view;
\ No newline at end of file
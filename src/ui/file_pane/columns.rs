@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use chrono::{DateTime, Local};
+use crate::file_system::config::PaneColumns;
+use crate::file_system::dir_size::DirSize;
+use crate::file_system::users;
+use crate::model::{file_name, Item, ItemType, UnixMeta};
+use crate::model::format::Formats;
+use crate::model::sort::SortKey;
+
+/// What a cell needs besides its item.
+pub struct CellContext<'a> {
+    pub formats: &'a Formats,
+    pub dir_sizes: &'a HashMap<PathBuf, DirSize>,
+    pub now: DateTime<Local>,
+}
+
+/// Supplies one kind of column to the file panes.
+pub trait ColumnProvider: Sync {
+    /// Stable name, as used in the config.
+    fn id(&self) -> &'static str;
+    fn title(&self) -> &'static str;
+    fn default_width(&self) -> f32;
+    fn sort_key(&self) -> Option<SortKey> {
+        None
+    }
+    fn text(&self, item: &Item, cx: &CellContext) -> String;
+}
+
+/// A column that is a plain function of the item.
+struct Builtin {
+    id: &'static str,
+    title: &'static str,
+    width: f32,
+    sort_key: Option<SortKey>,
+    text: fn(&Item, &CellContext) -> String,
+}
+
+impl ColumnProvider for Builtin {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn title(&self) -> &'static str {
+        self.title
+    }
+
+    fn default_width(&self) -> f32 {
+        self.width
+    }
+
+    fn sort_key(&self) -> Option<SortKey> {
+        self.sort_key
+    }
+
+    fn text(&self, item: &Item, cx: &CellContext) -> String {
+        (self.text)(item, cx)
+    }
+}
+
+/// Every column a pane can show, in the order the header menu lists them.
+pub static PROVIDERS: &[&dyn ColumnProvider] = &[
+    &Builtin { id: "icon", title: "Icon", width: 30.0, sort_key: None, text: icon },
+    &Builtin { id: "name", title: "Name", width: 100.0, sort_key: Some(SortKey::Name), text: name },
+    &Builtin { id: "extension", title: "Extension", width: 60.0, sort_key: Some(SortKey::Extension), text: extension },
+    &Builtin { id: "size", title: "Size", width: 80.0, sort_key: Some(SortKey::Size), text: size },
+    &Builtin { id: "modified", title: "Modified", width: 200.0, sort_key: Some(SortKey::Modified), text: modified },
+    &Builtin { id: "created", title: "Created", width: 200.0, sort_key: None, text: created },
+    &Builtin { id: "accessed", title: "Accessed", width: 200.0, sort_key: None, text: accessed },
+    &Builtin { id: "permissions", title: "Permissions", width: 90.0, sort_key: None, text: mode_string },
+    &Builtin { id: "mode", title: "Octal", width: 50.0, sort_key: None, text: octal_mode },
+    &Builtin { id: "owner", title: "Owner", width: 70.0, sort_key: None, text: owner },
+    &Builtin { id: "group", title: "Group", width: 70.0, sort_key: None, text: group },
+    &Builtin { id: "inode", title: "Inode", width: 80.0, sort_key: None, text: inode },
+    &Builtin { id: "links", title: "Links", width: 40.0, sort_key: None, text: links },
+    &Builtin { id: "link_target", title: "Link target", width: 150.0, sort_key: None, text: link_target },
+    &Builtin { id: "original_path", title: "Original path", width: 200.0, sort_key: None, text: original_path },
+    &Builtin { id: "deleted", title: "Deleted", width: 200.0, sort_key: None, text: deleted },
+];
+
+fn icon(item: &Item, _: &CellContext) -> String {
+    let icon = match item.item_type {
+        _ if item.is_parent_link() => "⬆",
+        ItemType::File => "📄",
+        ItemType::Directory => "📁",
+        ItemType::Symlink { broken: false, .. } => "🔗",
+        ItemType::Symlink { broken: true, .. } => "⛓",
+        ItemType::Socket => "🔌",
+        ItemType::Fifo => "📨",
+        ItemType::BlockDevice => "💽",
+        ItemType::CharDevice => "🖮",
+    };
+    icon.to_string()
+}
+
+fn name(item: &Item, _: &CellContext) -> String {
+    item.display_name()
+}
+
+fn extension(item: &Item, _: &CellContext) -> String {
+    match item.item_type {
+        ItemType::Directory => String::new(),
+        _ => std::path::Path::new(&item.name).extension().map(file_name::escape).unwrap_or_default(),
+    }
+}
+
+/// Directories show what they hold once measured, with an ellipsis while
+/// that is going on.
+fn size(item: &Item, cx: &CellContext) -> String {
+    if item.item_type == ItemType::Directory {
+        return match cx.dir_sizes.get(&item.path) {
+            Some(size) if size.done => cx.formats.size.format(size.bytes),
+            Some(DirSize { bytes: 0, .. }) => "…".into(),
+            Some(size) => format!("{}…", cx.formats.size.format(size.bytes)),
+            None => "<DIR>".into(),
+        };
+    }
+    item.size.map(|size| cx.formats.size.format(size)).unwrap_or_else(|| "?".into())
+}
+
+fn modified(item: &Item, cx: &CellContext) -> String {
+    date(item.modified, cx)
+}
+
+fn created(item: &Item, cx: &CellContext) -> String {
+    unix(item, |unix| date(unix.created, cx))
+}
+
+fn accessed(item: &Item, cx: &CellContext) -> String {
+    unix(item, |unix| date(unix.accessed, cx))
+}
+
+fn deleted(item: &Item, cx: &CellContext) -> String {
+    item.trash_info.as_ref().map(|info| date(info.deleted, cx)).unwrap_or_default()
+}
+
+fn date(date: Option<DateTime<Local>>, cx: &CellContext) -> String {
+    date.map(|date| cx.formats.date.format(&date, &cx.now)).unwrap_or_else(|| "?".into())
+}
+
+fn mode_string(item: &Item, _: &CellContext) -> String {
+    unix(item, |unix| permissions(unix.mode, &item.item_type))
+}
+
+fn octal_mode(item: &Item, _: &CellContext) -> String {
+    unix(item, |unix| format!("{:04o}", unix.mode))
+}
+
+fn owner(item: &Item, _: &CellContext) -> String {
+    unix(item, |unix| users::user_name(unix.uid).unwrap_or_else(|| unix.uid.to_string()))
+}
+
+fn group(item: &Item, _: &CellContext) -> String {
+    unix(item, |unix| users::group_name(unix.gid).unwrap_or_else(|| unix.gid.to_string()))
+}
+
+fn inode(item: &Item, _: &CellContext) -> String {
+    unix(item, |unix| unix.inode.to_string())
+}
+
+fn links(item: &Item, _: &CellContext) -> String {
+    unix(item, |unix| unix.links.to_string())
+}
+
+/// `?` for entries whose own metadata could not be read.
+fn unix(item: &Item, text: impl FnOnce(&UnixMeta) -> String) -> String {
+    item.unix.as_ref().map(text).unwrap_or_else(|| "?".into())
+}
+
+fn link_target(item: &Item, _: &CellContext) -> String {
+    match &item.item_type {
+        ItemType::Symlink { target, broken: false, .. } => file_name::escape(target.as_os_str()),
+        ItemType::Symlink { target, broken: true, .. } => format!("{} (broken)", file_name::escape(target.as_os_str())),
+        _ => String::new(),
+    }
+}
+
+fn original_path(item: &Item, _: &CellContext) -> String {
+    item.trash_info.as_ref()
+        .map(|info| file_name::escape(info.original_path.as_os_str()))
+        .unwrap_or_default()
+}
+
+/// `ls -l` style mode string, like `drwxr-sr-x`.
+pub fn permissions(mode: u32, item_type: &ItemType) -> String {
+    let kind = match item_type {
+        ItemType::File => '-',
+        ItemType::Directory => 'd',
+        ItemType::Symlink { .. } => 'l',
+        ItemType::Socket => 's',
+        ItemType::Fifo => 'p',
+        ItemType::BlockDevice => 'b',
+        ItemType::CharDevice => 'c',
+    };
+
+    let mut text = String::from(kind);
+    for (shift, special, mark) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = mode >> shift;
+        text.push(if bits & 4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 2 != 0 { 'w' } else { '-' });
+        text.push(match (mode & special != 0, bits & 1 != 0) {
+            (true, true) => mark,
+            (true, false) => mark.to_ascii_uppercase(),
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    text
+}
+
+#[derive(Copy, Clone)]
+pub struct Column {
+    pub provider: &'static dyn ColumnProvider,
+    pub width: f32,
+}
+
+impl Column {
+    /// The column with this id, at its default width.
+    pub fn new(id: &str) -> Option<Column> {
+        let provider = *PROVIDERS.iter().find(|provider| provider.id() == id)?;
+        Some(Column { provider, width: provider.default_width() })
+    }
+
+    pub fn id(&self) -> &'static str {
+        self.provider.id()
+    }
+}
+
+impl fmt::Debug for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.id(), self.width)
+    }
+}
+
+/// The columns a pane shows in ordinary directories and in the trash.
+#[derive(Clone, Debug)]
+pub struct ColumnLayout {
+    pub files: Vec<Column>,
+    pub trash: Vec<Column>,
+}
+
+impl Default for ColumnLayout {
+    fn default() -> Self {
+        let columns = |ids: &[&str]| ids.iter().filter_map(|id| Column::new(id)).collect();
+        ColumnLayout {
+            files: columns(&["icon", "name", "size", "modified", "link_target"]),
+            trash: columns(&["icon", "name", "original_path", "deleted", "size"]),
+        }
+    }
+}
+
+impl ColumnLayout {
+    /// The layout a pane saved, with defaults for whatever is missing or
+    /// unreadable.
+    pub fn from_config(saved: &PaneColumns) -> Self {
+        let mut layout = ColumnLayout::default();
+        if let Some(files) = saved.files.as_deref().and_then(parse_layout) {
+            layout.files = files;
+        }
+        if let Some(trash) = saved.trash.as_deref().and_then(parse_layout) {
+            layout.trash = trash;
+        }
+        layout
+    }
+
+    pub fn to_config(&self) -> PaneColumns {
+        PaneColumns {
+            files: Some(layout_spec(&self.files)),
+            trash: Some(layout_spec(&self.trash)),
+        }
+    }
+}
+
+/// Reads columns written by `layout_spec`. `None` unless every column is
+/// known and the Name column is among them.
+pub fn parse_layout(spec: &str) -> Option<Vec<Column>> {
+    let columns = spec.split_whitespace()
+        .map(|column| {
+            let (id, width) = column.split_once(':')?;
+            let width: f32 = width.parse().ok().filter(|w: &f32| w.is_finite() && *w > 0.0)?;
+            Some(Column { width, ..Column::new(id)? })
+        })
+        .collect::<Option<Vec<Column>>>()?;
+    columns.iter().any(|column| column.id() == "name").then_some(columns)
+}
+
+/// Columns as `id:width` pairs, separated by spaces.
+pub fn layout_spec(columns: &[Column]) -> String {
+    columns.iter()
+        .map(|column| format!("{}:{}", column.id(), column.width))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
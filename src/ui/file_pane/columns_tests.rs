@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+    use chrono::Local;
+    use crate::file_system::config::PaneColumns;
+    use crate::file_system::dir_size::DirSize;
+    use crate::model::format::Formats;
+    use crate::model::{Item, ItemType, UnixMeta};
+    use crate::ui::file_pane::columns::{layout_spec, parse_layout, permissions, CellContext, Column, ColumnLayout, PROVIDERS};
+
+    fn item(item_type: ItemType, mode: u32) -> Item {
+        Item {
+            name: "archive.tar.gz".into(),
+            path: Path::new("/d/archive.tar.gz").to_path_buf(),
+            item_type,
+            size: Some(2048),
+            modified: None,
+            trash_info: None,
+            unix: Some(UnixMeta { mode, uid: 0, gid: 0, inode: 42, links: 3, accessed: None, created: None }),
+        }
+    }
+
+    fn text(id: &str, item: &Item) -> String {
+        let formats = Formats::default();
+        Column::new(id).unwrap().provider.text(item, &CellContext { formats: &formats, dir_sizes: &HashMap::new(), now: Local::now() })
+    }
+
+    #[test]
+    fn permission_strings() {
+        assert_eq!(permissions(0o755, &ItemType::Directory), "drwxr-xr-x");
+        assert_eq!(permissions(0o640, &ItemType::File), "-rw-r-----");
+        assert_eq!(permissions(0o4755, &ItemType::File), "-rwsr-xr-x");
+        assert_eq!(permissions(0o2644, &ItemType::File), "-rw-r-Sr--");
+        assert_eq!(permissions(0o1777, &ItemType::Directory), "drwxrwxrwt");
+        assert_eq!(permissions(0o777, &ItemType::Symlink { target: "x".into(), broken: false, to_dir: false }), "lrwxrwxrwx");
+    }
+
+    #[test]
+    fn cells_show_item_details() {
+        let item = item(ItemType::File, 0o644);
+
+        assert_eq!(text("permissions", &item), "-rw-r--r--");
+        assert_eq!(text("mode", &item), "0644");
+        assert_eq!(text("owner", &item), "root");
+        assert_eq!(text("inode", &item), "42");
+        assert_eq!(text("links", &item), "3");
+        assert_eq!(text("extension", &item), "gz");
+        assert_eq!(text("size", &item), "2.0 KiB");
+        assert_eq!(text("created", &item), "?");
+        assert_eq!(text("deleted", &item), "");
+    }
+
+    #[test]
+    fn directories_show_measured_sizes() {
+        let dir = item(ItemType::Directory, 0o755);
+        let formats = Formats::default();
+        let size = Column::new("size").unwrap().provider;
+        let mut dir_sizes = HashMap::new();
+        let mut text = |measured| {
+            dir_sizes.insert(dir.path.clone(), measured);
+            size.text(&dir, &CellContext { formats: &formats, dir_sizes: &dir_sizes, now: Local::now() })
+        };
+
+        assert_eq!(text(DirSize { bytes: 0, done: false }), "…");
+        assert_eq!(text(DirSize { bytes: 3072, done: false }), "3.0 KiB…");
+        assert_eq!(text(DirSize { bytes: 3072, done: true }), "3.0 KiB");
+        assert_eq!(size.text(&dir, &CellContext { formats: &formats, dir_sizes: &HashMap::new(), now: Local::now() }), "<DIR>");
+    }
+
+    #[test]
+    fn unreadable_metadata_shows_unknown() {
+        let item = Item { unix: None, ..item(ItemType::File, 0) };
+
+        assert_eq!(text("permissions", &item), "?");
+        assert_eq!(text("owner", &item), "?");
+    }
+
+    #[test]
+    fn ids_are_unique() {
+        for (i, provider) in PROVIDERS.iter().enumerate() {
+            assert!(PROVIDERS[i + 1..].iter().all(|other| other.id() != provider.id()));
+        }
+    }
+
+    #[test]
+    fn layouts_round_trip_and_need_a_name_column() {
+        let columns = parse_layout("icon:30 name:150.5 permissions:90").unwrap();
+        assert_eq!(columns.iter().map(Column::id).collect::<Vec<_>>(), ["icon", "name", "permissions"]);
+        assert_eq!(columns[1].width, 150.5);
+        assert_eq!(layout_spec(&columns), "icon:30 name:150.5 permissions:90");
+
+        assert!(parse_layout("icon:30 size:80").is_none());
+        assert!(parse_layout("name:100 bogus:10").is_none());
+        assert!(parse_layout("name:-5").is_none());
+        assert!(parse_layout("name").is_none());
+    }
+
+    #[test]
+    fn unreadable_saved_layouts_fall_back_to_defaults() {
+        let saved = PaneColumns { files: Some("name:300 owner:50".into()), trash: Some("size:10".into()) };
+
+        let layout = ColumnLayout::from_config(&saved);
+        assert_eq!(layout_spec(&layout.files), "name:300 owner:50");
+        assert_eq!(layout_spec(&layout.trash), layout_spec(&ColumnLayout::default().trash));
+        assert_eq!(ColumnLayout::from_config(&layout.to_config()).files.len(), 2);
+    }
+}
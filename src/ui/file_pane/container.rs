@@ -1,104 +1,825 @@
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::sync::mpsc as std_mpsc;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use log::info;
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use crate::file_system::dir_size::{self, DirSize};
+use crate::file_system::disk_space::DiskSpace;
 use crate::file_system::file_mutator::FileMutator;
-use crate::file_system::navigator::Navigator;
-use crate::file_system::watcher::FileWatcher;
-use crate::model::{Column};
+use crate::file_system::journal::JournalOp;
+use crate::file_system::multi_rename::{MultiRename, PlannedRename};
+use crate::file_system::navigator::{ListError, Listing, Navigator};
+use crate::file_system::path_input;
+use crate::file_system::operations::Operation;
+use crate::file_system::trash::Trash;
+use crate::file_system::watcher::{FileChange, FileWatcher};
+use crate::model::{file_name, Item};
+use crate::model::conflict::{Conflict, ConflictPolicy, LinkPolicy, TransferKind};
 use crate::model::pane_controls::PaneControlsEvent;
+use crate::model::sort::SortOrder;
+use crate::ui::conflict_dialog::conflict_dialog::ConflictResolution;
+use crate::ui::multi_rename::multi_rename_dialog::MultiRenameDialog;
+use crate::ui::prompt_dialog::prompt_dialog::Prompt;
+use crate::ui::file_pane::columns::{Column, ColumnLayout};
 use crate::ui::file_pane::view::{View, NavigatedEvent};
 
+/// A copy/move/restore being prepared. It only outlives a single call while
+/// it is waiting for the user to resolve a conflict on the front entry; once
+/// every entry has a policy it becomes a background job.
+struct PendingTransfer {
+    kind: TransferKind,
+    links: LinkPolicy,
+    restore: bool,
+    queue: VecDeque<(PathBuf, PathBuf)>,
+    policy: ConflictPolicy,
+    resolved: Vec<Operation>,
+}
+
+/// A listing streaming in from a blocking task.
+struct Loading {
+    generation: u64,
+    /// Lets the task send its next batch; dropping it stops the task.
+    ack: std_mpsc::Sender<()>,
+    /// Entries so far, when the shown ones are only replaced at the end.
+    buffered: Option<Vec<Item>>,
+    count: usize,
+    /// Where to put the cursor once the entry shows up.
+    focus: Option<PathBuf>,
+}
+
+/// A directory size being measured in the background.
+struct Measuring {
+    generation: u64,
+    /// Set to stop the measurement.
+    cancel: Arc<AtomicBool>,
+}
+
+/// Entries in the first batch of a listing; later batches double in size,
+/// up to `MAX_BATCH`.
+const FIRST_BATCH: usize = 256;
+const MAX_BATCH: usize = 32768;
+
+/// Work handed to the operation queue, as (title, steps).
+pub type Job = (String, Vec<Operation>);
+
 pub struct Container {
     pub view: View,
     pub navigator: Navigator,
     pub receiver: mpsc::Receiver<NavigatedEvent>,
     watcher: FileWatcher,
+    transfer: Option<PendingTransfer>,
+    pending_deletion: Option<Vec<Item>>,
+    mkdir_prompt: Option<Prompt>,
+    multi_rename: Option<MultiRenameDialog>,
+    trash: Trash,
+    jobs: Vec<Job>,
+    recorded: Vec<JournalOp>,
+    loading: Option<Loading>,
+    /// Tells batches of an abandoned listing apart.
+    generation: u64,
+    layout: ColumnLayout,
+    layout_changed: bool,
+    measuring: HashMap<PathBuf, Measuring>,
+    /// Tells progress of a stopped measurement apart.
+    measurements: u64,
 }
 
 impl Container {
     pub fn new(navigator: Navigator) -> Self {
-        let items = navigator.list_contents();
-        let columns = Self::default_columns();
-        let (tx, rx) = mpsc::channel(1);
+        Self::with_trash(navigator, Trash::new())
+    }
+
+    pub(crate) fn with_trash(navigator: Navigator, trash: Trash) -> Self {
+        let layout = ColumnLayout::default();
+        // room for key presses next to a pending batch
+        let (tx, rx) = mpsc::channel(8);
         let breadcrumbs = navigator.breadcrumbs();
 
         let current_path = navigator.current_path.clone();
-        let watcher = FileWatcher::new(&tx, &*current_path)
+        let watcher = FileWatcher::new(&tx, &current_path)
             .expect("failed to init file watcher");
 
-        let view = View::new(items, columns, tx, breadcrumbs);
-
-        Self { view, navigator, receiver: rx, watcher }
+        let mut container = Self {
+            view: View::new(Vec::new(), layout.files.clone(), tx, breadcrumbs),
+            navigator,
+            receiver: rx,
+            watcher,
+            transfer: None,
+            pending_deletion: None,
+            mkdir_prompt: None,
+            multi_rename: None,
+            trash,
+            jobs: Vec::new(),
+            recorded: Vec::new(),
+            loading: None,
+            generation: 0,
+            layout,
+            layout_changed: false,
+            measuring: HashMap::new(),
+            measurements: 0,
+        };
+        container.navigate(|_| Ok(()));
+        container
     }
 
-    pub fn handle_navigation_event(&mut self, event: &NavigatedEvent) {
+    pub fn handle_navigation_event(&mut self, event: NavigatedEvent) {
         match event {
-            NavigatedEvent::DirectoryOpened(path) => {
-                self.navigator.open_dir(path);
-                let _ = self.watcher.watch_path(&self.navigator.current_path);
-                self.refresh_items();
-                self.view.select_single(0);
-            },
+            NavigatedEvent::DirectoryOpened(path) => self.navigate(|navigator| {
+                navigator.open_dir(&path);
+                Ok(())
+            }),
+            NavigatedEvent::LinkFollowed(link) => self.follow_link(&link),
             NavigatedEvent::TraversedUp => {
-                self.navigator.go_up();
-                let _ = self.watcher.watch_path(&self.navigator.current_path);
-                self.refresh_items();
-                self.view.select_single(0);
+                let left = self.navigator.current_path.clone();
+                self.navigate(|navigator| {
+                    navigator.go_up();
+                    Ok(())
+                });
+                self.focus(left);
             },
             NavigatedEvent::SelectionMoved { index, selection, additive, direction } => {
-                self.view.handle_selection_moved(*index, *selection, *additive, *direction);
+                self.view.handle_selection_moved(index, selection, additive, direction);
+            },
+            NavigatedEvent::FilesUpdated => self.refresh_items(),
+            NavigatedEvent::FilesChanged(changes) => {
+                for path in changes.iter().flat_map(FileChange::paths) {
+                    self.forget_sizes(path);
+                }
+                self.apply_changes(&changes);
             },
-            NavigatedEvent::FilesUpdated => {
-                self.refresh_items();
-                if self.view.item_count() > 0 {
-                    self.view.select_single(0);
+            NavigatedEvent::ItemsListed { generation, items, sorted_by, done } => {
+                self.items_listed(generation, items, sorted_by, done)
+            },
+            NavigatedEvent::RenameRequested => {
+                if !self.navigator.in_trash() {
+                    self.view.start_rename();
                 }
             },
+            NavigatedEvent::RenameSubmitted { path, new_name } => self.rename(&path, &new_name),
+            NavigatedEvent::SortChanged(sort) => self.view.set_sort(sort),
+            // the double pane takes these to update both panes
+            NavigatedEvent::FormatsChanged(formats) => self.view.set_formats(formats),
+            NavigatedEvent::ColumnsChanged(columns) => self.set_columns(columns),
+            NavigatedEvent::PathEntered(text) => self.open_entered_path(&text),
+            NavigatedEvent::PathCompletionRequested(text) => {
+                let completion = path_input::complete(&text, &self.navigator.current_path);
+                self.view.path_completed(&text, completion);
+            },
+            NavigatedEvent::SizeRequested { paths, cross_devices } => self.measure_sizes(paths, cross_devices),
+            NavigatedEvent::SizeMeasured { generation, path, size } => self.size_measured(generation, path, size),
         }
     }
 
-    pub fn handle_pane_controls_event(&mut self, event: &PaneControlsEvent, destination: &PathBuf) {
+    fn rename(&mut self, path: &Path, new_name: &str) {
+        let new_name = file_name::unescape(new_name);
+        if path.file_name() == Some(new_name.as_os_str()) {
+            self.view.finish_rename();
+            return;
+        }
+
+        match FileMutator::durable_rename(path, &new_name) {
+            Ok(renamed) => {
+                info!("Renamed {:?} -> {:?}", path, renamed);
+                self.recorded.push(JournalOp::Move(vec![(path.to_path_buf(), renamed.clone())]));
+                self.view.finish_rename();
+                self.relist([path, renamed.as_path()]);
+                if let Some(index) = self.view.index_of(&renamed) {
+                    self.view.select_single(index);
+                }
+            }
+            Err(e) => self.view.rename_failed(e.to_string()),
+        }
+    }
+
+    pub fn handle_pane_controls_event(&mut self, event: &PaneControlsEvent, destination: &Path) {
         match event {
-            PaneControlsEvent::MoveSelected => self.move_selected(destination),
+            PaneControlsEvent::CopySelected(links) => self.start_transfer(TransferKind::Copy, *links, destination),
+            PaneControlsEvent::MoveSelected(links) => self.start_transfer(TransferKind::Move, *links, destination),
+            PaneControlsEvent::MakeDirectory => {
+                if !self.navigator.in_trash() {
+                    self.mkdir_prompt = Some(Prompt::default());
+                }
+            }
+            PaneControlsEvent::MultiRename => {
+                let items = self.view.get_selected_items();
+                if !items.is_empty() && !self.navigator.in_trash() {
+                    let existing = self.view.items().iter().map(Item::display_name).collect();
+                    self.multi_rename = Some(MultiRenameDialog::new(items, existing));
+                }
+            }
+            PaneControlsEvent::DeleteSelected if !self.navigator.in_trash() => self.trash_selected(),
+            PaneControlsEvent::DeleteSelected | PaneControlsEvent::DeleteSelectedPermanently => {
+                self.ask_deletion(self.view.get_selected_items());
+            }
+            PaneControlsEvent::OpenTrash => {
+                let trash = self.trash.clone();
+                self.navigate(|navigator| navigator.open_trash(&trash));
+            }
+            PaneControlsEvent::RestoreSelected => self.restore_selected(),
+            PaneControlsEvent::EmptyTrash => {
+                if self.navigator.in_trash() {
+                    let trashed = self.view.items().iter().filter(|item| item.trash_info.is_some()).cloned().collect();
+                    self.ask_deletion(trashed);
+                }
+            }
+            // handled by the double pane, which owns the journal
+            PaneControlsEvent::Undo | PaneControlsEvent::Redo => {}
+        }
+    }
+
+    fn ask_deletion(&mut self, mut items: Vec<Item>) {
+        // ".." is the directory the pane is in, or the one the trash was
+        // opened from
+        items.retain(|item| !item.is_parent_link());
+        if !items.is_empty() {
+            self.pending_deletion = Some(items);
+        }
+    }
+
+    /// Items waiting for the user to confirm permanent deletion.
+    pub fn pending_deletion(&self) -> Option<&[Item]> {
+        self.pending_deletion.as_deref()
+    }
+
+    pub fn confirm_deletion(&mut self, confirmed: bool) {
+        let Some(mut items) = self.pending_deletion.take() else { return };
+        if !confirmed {
+            return;
+        }
+        // in the trash, only what was trashed gets deleted
+        let in_trash = self.navigator.in_trash();
+        items.retain(|item| !item.is_parent_link() && (!in_trash || item.trash_info.is_some()));
+        if items.is_empty() {
+            return;
+        }
+
+        let operations = items.iter()
+            .map(|item| match item.trash_info {
+                Some(_) => Operation::Purge { trash: self.trash.clone(), trashed: item.path.clone() },
+                None => Operation::Delete { path: item.path.clone() },
+            })
+            .collect();
+        self.move_cursor_past(&items);
+        self.jobs.push((Self::job_title("Deleting", &items), operations));
+    }
+
+    /// Jobs queued since the last call, for the operation queue to run.
+    pub fn take_jobs(&mut self) -> Vec<Job> {
+        std::mem::take(&mut self.jobs)
+    }
+
+    /// Operations done directly by this pane since the last call, for the
+    /// journal.
+    pub fn take_recorded(&mut self) -> Vec<JournalOp> {
+        std::mem::take(&mut self.recorded)
+    }
+
+    /// The "new folder" name prompt, while it is open.
+    pub fn mkdir_prompt(&mut self) -> Option<&mut Prompt> {
+        self.mkdir_prompt.as_mut()
+    }
+
+    pub fn is_creating_directory(&self) -> bool {
+        self.mkdir_prompt.is_some()
+    }
+
+    pub fn cancel_mkdir(&mut self) {
+        self.mkdir_prompt = None;
+    }
+
+    /// Creates the directory typed into the prompt and puts the cursor on it.
+    /// On failure the prompt stays open with the error.
+    pub fn submit_mkdir(&mut self) {
+        let Some(prompt) = self.mkdir_prompt.as_mut() else { return };
+        let name = prompt.text.trim();
+        let missing: Vec<PathBuf> = Path::new(name).ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|p| self.navigator.current_path.join(p))
+            .filter(|p| fs::symlink_metadata(p).is_err())
+            .collect();
+
+        match FileMutator::durable_create_dir(&self.navigator.current_path, name) {
+            Ok(created) => {
+                info!("Created directory {:?}", created);
+                self.mkdir_prompt = None;
+                self.recorded.push(JournalOp::CreateDirs(missing.into_iter().rev().collect()));
+
+                let top_level = created.ancestors()
+                    .find(|p| p.parent() == Some(self.navigator.current_path.as_path()))
+                    .map(Path::to_path_buf)
+                    .unwrap_or(created);
+                self.relist([top_level.as_path()]);
+                if let Some(index) = self.view.index_of(&top_level) {
+                    self.view.select_single(index);
+                }
+            }
+            Err(e) => prompt.error = Some(e.to_string()),
         }
     }
 
-    fn move_selected(&mut self, destination: &PathBuf) {
-        let selected_items = self.view.get_selected_items();
-        let original_max_index = self.view.get_selected_indices().iter().max().cloned().unwrap_or(0);
+    pub fn multi_rename_dialog(&mut self) -> Option<&mut MultiRenameDialog> {
+        self.multi_rename.as_mut()
+    }
+
+    pub fn is_multi_renaming(&self) -> bool {
+        self.multi_rename.is_some()
+    }
+
+    pub fn close_multi_rename(&mut self) {
+        self.multi_rename = None;
+    }
+
+    /// Renames the whole batch, or nothing if any rename fails.
+    pub fn apply_multi_rename(&mut self, plan: &[PlannedRename]) {
+        match MultiRename::apply(plan) {
+            Ok(()) => {
+                info!("Renamed {} items", plan.len());
+                self.multi_rename = None;
+                let moves = plan.iter()
+                    .filter(|p| p.old_name != p.new_name)
+                    .map(|p| (p.from.clone(), p.target()))
+                    .collect();
+                self.recorded.push(JournalOp::Move(moves));
+
+                let cursor_path = self.view.get_cursor_item().map(|item| item.path.clone());
+                let renamed_cursor = plan.iter()
+                    .find(|p| Some(&p.from) == cursor_path.as_ref())
+                    .map(PlannedRename::target);
+                let targets: Vec<PathBuf> = plan.iter().map(PlannedRename::target).collect();
+                self.relist(plan.iter().map(|p| p.from.as_path()).chain(targets.iter().map(PathBuf::as_path)));
+                if let Some(index) = renamed_cursor.and_then(|path| self.view.index_of(&path)) {
+                    self.view.select_single(index);
+                }
+            }
+            Err(e) => {
+                if let Some(dialog) = self.multi_rename.as_mut() {
+                    dialog.apply_failed(e.to_string());
+                }
+            }
+        }
+    }
+
+    fn trash_selected(&mut self) {
+        let items = self.view.get_selected_items();
+        if items.is_empty() {
+            return;
+        }
+
+        let operations = items.iter()
+            .map(|item| Operation::Trash { trash: self.trash.clone(), path: item.path.clone() })
+            .collect();
+        self.move_cursor_past(&items);
+        self.jobs.push((Self::job_title("Trashing", &items), operations));
+    }
+
+    fn job_title(verb: &str, items: &[Item]) -> String {
+        match items {
+            [item] => format!("{} {}", verb, item.display_name()),
+            items => format!("{} {} items", verb, items.len()),
+        }
+    }
+
+    pub fn pending_conflict(&self) -> Option<Conflict> {
+        let transfer = self.transfer.as_ref()?;
+        let (src, dst) = transfer.queue.front()?;
+
+        Some(Conflict { kind: transfer.kind, src: src.clone(), dst: dst.clone() })
+    }
+
+    pub fn resolve_conflict(&mut self, resolution: ConflictResolution) {
+        match resolution {
+            ConflictResolution::Resolve { policy, apply_to_all } => {
+                if apply_to_all && let Some(transfer) = self.transfer.as_mut() {
+                    transfer.policy = policy;
+                }
+                self.continue_transfer(Some(policy));
+            }
+            ConflictResolution::Cancel => self.transfer = None,
+        }
+    }
+
+    fn start_transfer(&mut self, kind: TransferKind, links: LinkPolicy, destination: &Path) {
+        let queue = self.view.get_selected_items().into_iter()
+            .map(|item| {
+                let dst = destination.join(item.path.file_name().unwrap_or_default());
+                (item.path, dst)
+            })
+            .collect();
+        self.begin_transfer(kind, links, false, queue);
+    }
+
+    fn restore_selected(&mut self) {
+        let queue = self.view.get_selected_items().into_iter()
+            .filter_map(|item| Some((item.path, item.trash_info?.original_path)))
+            .collect();
+        self.begin_transfer(TransferKind::Move, LinkPolicy::Preserve, true, queue);
+    }
+
+    fn begin_transfer(&mut self, kind: TransferKind, links: LinkPolicy, restore: bool, queue: VecDeque<(PathBuf, PathBuf)>) {
+        if queue.is_empty() {
+            return;
+        }
 
-        for item in selected_items {
-            let src = self.navigator.current_path.join(&item.name);
-            let dst = destination.join(&item.name);
+        self.transfer = Some(PendingTransfer {
+            kind,
+            links,
+            restore,
+            queue,
+            policy: ConflictPolicy::Ask,
+            resolved: Vec::new(),
+        });
+        self.continue_transfer(None);
+    }
 
-            info!("Moving {:?} -> {:?}", src, dst);
+    /// Settles a policy for each entry, stopping at the first one that needs
+    /// the user. The job is only queued once all entries are settled.
+    fn continue_transfer(&mut self, mut policy_once: Option<ConflictPolicy>) {
+        let Some(transfer) = self.transfer.as_mut() else { return };
 
-            if let Err(e) = FileMutator::durable_move(&src, &dst) {
-                info!("Failed to move {:?}: {:?}", src, e);
+        while let Some((_, dst)) = transfer.queue.front() {
+            let policy = policy_once.take().unwrap_or(transfer.policy);
+            if policy == ConflictPolicy::Ask && fs::symlink_metadata(dst).is_ok() {
+                return;
             }
+
+            let (src, dst) = transfer.queue.pop_front().unwrap();
+            transfer.resolved.push(if transfer.restore {
+                Operation::Restore { trash: self.trash.clone(), trashed: src, original: dst, policy }
+            } else {
+                Operation::Transfer { src, dst, kind: transfer.kind, policy, links: transfer.links }
+            });
+        }
+
+        let Some(transfer) = self.transfer.take() else { return };
+        if transfer.resolved.is_empty() {
+            return;
+        }
+
+        let items = self.view.get_selected_items();
+        let verb = match (transfer.restore, transfer.kind) {
+            (true, _) => "Restoring",
+            (false, TransferKind::Copy) => "Copying",
+            (false, TransferKind::Move) => "Moving",
+        };
+        if transfer.restore || transfer.kind == TransferKind::Move {
+            self.move_cursor_past(&items);
+        }
+        info!("{} {} entries", verb, transfer.resolved.len());
+        self.jobs.push((Self::job_title(verb, &items), transfer.resolved));
+    }
+
+    /// Puts the cursor on the first entry after `leaving` that stays, so it
+    /// lands there once the watcher reports the removal.
+    fn move_cursor_past(&mut self, leaving: &[Item]) {
+        let stays = |item: &&Item| !leaving.iter().any(|l| l.path == item.path);
+        let last_leaving = self.view.get_selected_indices().iter().max().cloned().unwrap_or(0);
+
+        let items = self.view.items();
+        let next = items[last_leaving.min(items.len())..].iter().find(stays)
+            .or_else(|| items[..last_leaving.min(items.len())].iter().rev().find(stays))
+            .map(|item| item.path.clone());
+        if let Some(index) = next.and_then(|path| self.view.index_of(&path)) {
+            self.view.select_single(index);
         }
+    }
+
+    /// Opens a link's target if it is a directory; otherwise opens the
+    /// directory holding the target and puts the cursor on it.
+    fn follow_link(&mut self, link: &Path) {
+        let target = match Navigator::link_target(link) {
+            Ok(target) => target,
+            Err(e) => return self.view.set_error(Some(format!("Cannot read link {}: {}", link.display(), e))),
+        };
+
+        if target.is_dir() {
+            return self.navigate(|navigator| {
+                navigator.open_dir(&target);
+                Ok(())
+            });
+        }
+
+        let Some(dir) = target.parent() else { return };
+        self.navigate(|navigator| {
+            navigator.open_dir(dir);
+            Ok(())
+        });
+
+        if fs::symlink_metadata(&target).is_err() {
+            self.view.set_error(Some(format!("Link target {} does not exist", target.display())));
+        } else {
+            self.focus(target);
+        }
+    }
 
-        self.refresh_items();
+    /// Goes to a path typed into the path bar; a file opens its directory
+    /// with the cursor on it. Paths that cannot be used keep the editor open
+    /// with the reason.
+    fn open_entered_path(&mut self, text: &str) {
+        let path = match path_input::expand(text, &self.navigator.current_path) {
+            Ok(path) => path,
+            Err(e) => return self.view.path_edit_failed(e),
+        };
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) => return self.view.path_edit_failed(format!("{}: {}", path.display(), e)),
+        };
 
-        let count = self.view.item_count();
-        if count > 0 {
-            let next_index = (original_max_index + 1).min(count - 1);
-            self.view.select_single(next_index);
+        self.view.finish_path_edit();
+        if metadata.is_dir() {
+            self.navigate(|navigator| {
+                navigator.open_dir(&path);
+                Ok(())
+            });
+        } else if let Some(dir) = path.parent() {
+            self.navigate(|navigator| {
+                navigator.open_dir(dir);
+                Ok(())
+            });
+            self.focus(path.clone());
         }
     }
 
+    /// Moves to another location. If it cannot be listed, the pane stays
+    /// where it was and shows why.
+    fn navigate(&mut self, change: impl FnOnce(&mut Navigator) -> Result<(), ListError>) {
+        let previous = self.navigator.clone();
+        let listing = change(&mut self.navigator).and_then(|_| self.navigator.open_listing());
+
+        match listing {
+            Ok(listing) => {
+                let _ = self.watcher.watch_path(&self.navigator.current_path);
+                let current = self.navigator.current_path.clone();
+                self.forget_sizes(&current);
+                self.view.set_error(None);
+                self.view.set_columns(self.columns());
+                let parent = self.navigator.parent_item().into_iter().collect();
+                self.view.update_contents(parent, self.navigator.breadcrumbs());
+                self.view.select_single(0);
+                self.loading = None;
+                self.update_disk_space();
+                self.start_listing(listing, false);
+            }
+            Err(e) => {
+                info!("Staying in {:?}: {}", previous.current_path, e);
+                self.navigator = previous;
+                self.view.set_error(Some(e.to_string()));
+            }
+        }
+    }
+
+    /// Re-lists the current location in the background, replacing the shown
+    /// entries once complete. If the location cannot be opened, e.g. because
+    /// the directory was removed, the pane is emptied and shows the error.
+    /// Measured sizes of what it holds are dropped, as the watcher does not
+    /// see changes deeper down.
     pub(crate) fn refresh_items(&mut self) {
-        let items = self.navigator.list_contents();
-        let breadcrumbs = self.navigator.breadcrumbs();
-        self.view.update_contents(items, breadcrumbs);
+        let current = self.navigator.current_path.clone();
+        self.forget_sizes(&current);
+
+        match self.navigator.open_listing() {
+            Ok(listing) => self.start_listing(listing, true),
+            Err(e) => {
+                self.loading = None;
+                self.view.set_loading(None);
+                self.view.set_error(Some(e.to_string()));
+                self.view.update_contents(Vec::new(), self.navigator.breadcrumbs());
+                self.update_disk_space();
+            }
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    /// Streams `listing` into the view from a blocking task, abandoning any
+    /// listing still running. With `replace`, the shown entries stay until
+    /// the new ones are complete; otherwise each batch is shown as it comes.
+    fn start_listing(&mut self, listing: Listing, replace: bool) {
+        self.generation += 1;
+        let generation = self.generation;
+        let (ack, acks) = std_mpsc::channel();
+        let sender = self.view.sender().clone();
+        let sort = self.view.sort_order();
+        tokio::task::spawn_blocking(move || Self::stream(listing, generation, sort, sender, acks));
+
+        let focus = self.loading.take().and_then(|loading| loading.focus);
+        self.loading = Some(Loading { generation, ack, buffered: replace.then(Vec::new), count: 0, focus });
+        if !replace {
+            self.view.set_loading(Some(0));
+        }
+    }
+
+    /// Sends the listing in batches, each only once the previous one was
+    /// taken, so the channel stays free for the view. Each batch is sorted
+    /// here, leaving the view to merge it in. Growing batches keep small
+    /// directories to one batch and big ones to a few merges.
+    fn stream(
+        mut listing: Listing,
+        generation: u64,
+        sort: SortOrder,
+        sender: mpsc::Sender<NavigatedEvent>,
+        acks: std_mpsc::Receiver<()>,
+    ) {
+        let mut size = FIRST_BATCH;
+        let mut first = true;
+        loop {
+            let mut items: Vec<Item> = listing.by_ref().take(size).collect();
+            let done = items.len() < size;
+            // a dropped `ack` means the listing was abandoned
+            if !first && acks.recv().is_err() {
+                return;
+            }
+            sort.sort(&mut items);
+            let batch = NavigatedEvent::ItemsListed { generation, items, sorted_by: sort, done };
+            if sender.blocking_send(batch).is_err() || done {
+                return;
+            }
+            first = false;
+            size = (size * 2).min(MAX_BATCH);
+        }
+    }
+
+    fn items_listed(&mut self, generation: u64, items: Vec<Item>, sorted_by: SortOrder, done: bool) {
+        let Some(mut loading) = self.loading.take_if(|loading| loading.generation == generation) else { return };
+
+        loading.count += items.len();
+        match loading.buffered.as_mut() {
+            Some(buffer) => buffer.extend(items),
+            None => self.view.add_contents(items, sorted_by),
+        }
+
+        if !done {
+            self.view.set_loading(Some(loading.count));
+            let _ = loading.ack.send(());
+        } else {
+            if let Some(buffer) = loading.buffered.take() {
+                let items = self.navigator.parent_item().into_iter().chain(buffer).collect();
+                self.view.update_contents(items, self.navigator.breadcrumbs());
+            }
+            // the location could be read after all, e.g. after a failed refresh
+            self.view.set_error(None);
+            self.view.set_loading(None);
+            self.update_disk_space();
+        }
+
+        if let Some(index) = loading.focus.as_ref().and_then(|path| self.view.index_of(path)) {
+            self.view.select_single(index);
+            loading.focus = None;
+        }
+        if !done {
+            self.loading = Some(loading);
+        }
+    }
+
+    /// Puts the cursor on `path`, now or once the running listing reaches it.
+    fn focus(&mut self, path: PathBuf) {
+        if let Some(index) = self.view.index_of(&path) {
+            self.view.select_single(index);
+        } else if let Some(loading) = self.loading.as_mut() {
+            loading.focus = Some(path);
+        }
+    }
+
+    /// Patches the listing with what the watcher reported. Every touched
+    /// path is looked at again, so a batch holding e.g. a removal followed
+    /// by a re-creation ends up right. Changes outside the listed directory,
+    /// such as its own removal, and the trash fall back to a full re-listing.
+    fn apply_changes(&mut self, changes: &[FileChange]) {
+        let touched: BTreeSet<&Path> = changes.iter().flat_map(FileChange::paths).collect();
+        let outside = touched.iter().any(|path| path.parent() != Some(self.navigator.current_path.as_path()));
+        if self.navigator.in_trash() || outside {
+            return self.refresh_items();
+        }
+        self.relist(touched);
+    }
+
+    /// Looks at `paths` of the listed directory again and patches them into
+    /// the view, so it is up to date without waiting for the watcher.
+    fn relist<'a>(&mut self, paths: impl IntoIterator<Item = &'a Path>) {
+        if self.navigator.in_trash() {
+            return self.refresh_items();
+        }
+
+        let mut removed = Vec::new();
+        let mut updated = Vec::new();
+        for path in paths {
+            match Navigator::entry(path) {
+                Some(item) => updated.push(item),
+                None => removed.push(path.to_path_buf()),
+            }
+        }
+        self.view.patch_contents(&removed, updated);
+        self.update_disk_space();
+    }
+
+    /// Looks again at the space on the file system of the location.
+    fn update_disk_space(&mut self) {
+        self.view.set_disk_space(DiskSpace::of(&self.navigator.current_path).ok());
+    }
+
+    fn columns(&self) -> Vec<Column> {
+        if self.navigator.in_trash() {
+            self.layout.trash.clone()
+        } else {
+            self.layout.files.clone()
+        }
+    }
+
+    pub fn layout(&self) -> &ColumnLayout {
+        &self.layout
     }
 
-    fn default_columns() -> Vec<Column> {
-        vec![
-            Column { name: "Icon".into(),     width: 30.0 },
-            Column { name: "Name".into(),     width: 100.0 },
-            Column { name: "Size".into(),     width: 60.0 },
-            Column { name: "Modified".into(), width: 200.0 },
-        ]
+    pub fn set_layout(&mut self, layout: ColumnLayout) {
+        self.layout = layout;
+        self.view.set_columns(self.columns());
     }
-}
\ No newline at end of file
+
+    fn set_columns(&mut self, columns: Vec<Column>) {
+        if self.navigator.in_trash() {
+            self.layout.trash = columns;
+        } else {
+            self.layout.files = columns;
+        }
+        self.view.set_columns(self.columns());
+        self.layout_changed = true;
+    }
+
+    /// Whether the user changed the columns since the last call.
+    pub fn take_layout_changed(&mut self) -> bool {
+        std::mem::take(&mut self.layout_changed)
+    }
+
+    pub fn is_measuring(&self) -> bool {
+        !self.measuring.is_empty()
+    }
+
+    /// Measures the directories not measured or being measured yet, one
+    /// after the other in a blocking task. Their sizes are shown as they
+    /// grow and kept until the watcher reports a change to them.
+    fn measure_sizes(&mut self, paths: Vec<PathBuf>, cross_devices: bool) {
+        let mut queue = Vec::new();
+        for path in paths {
+            if self.measuring.contains_key(&path) || self.view.dir_size(&path).is_some_and(|size| size.done) {
+                continue;
+            }
+            self.measurements += 1;
+            let cancel = Arc::new(AtomicBool::new(false));
+            self.measuring.insert(path.clone(), Measuring { generation: self.measurements, cancel: cancel.clone() });
+            self.view.set_dir_size(path.clone(), DirSize { bytes: 0, done: false });
+            queue.push((path, self.measurements, cancel));
+        }
+        if queue.is_empty() {
+            return;
+        }
+
+        let sender = self.view.sender().clone();
+        tokio::task::spawn_blocking(move || {
+            for (path, generation, cancel) in queue {
+                let event = |bytes, done| NavigatedEvent::SizeMeasured {
+                    generation,
+                    path: path.clone(),
+                    size: DirSize { bytes, done },
+                };
+                // progress is dropped while the channel is full; a closed
+                // one means the pane is gone
+                let progress = |bytes| {
+                    !cancel.load(Ordering::Relaxed)
+                        && !matches!(sender.try_send(event(bytes, false)), Err(TrySendError::Closed(_)))
+                };
+                let Some(bytes) = dir_size::measure(&path, cross_devices, progress) else { continue };
+                if sender.blocking_send(event(bytes, true)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    fn size_measured(&mut self, generation: u64, path: PathBuf, size: DirSize) {
+        if self.measuring.get(&path).is_none_or(|measuring| measuring.generation != generation) {
+            return;
+        }
+        if size.done {
+            self.measuring.remove(&path);
+        }
+        self.view.set_dir_size(path, size);
+    }
+
+    /// Forgets the sizes `changed` may have altered: those of directories
+    /// holding it and of anything inside it. Their measurements stop.
+    fn forget_sizes(&mut self, changed: &Path) {
+        let affected = |dir: &Path| changed.starts_with(dir) || dir.starts_with(changed);
+        self.measuring.retain(|dir, measuring| {
+            let keep = !affected(dir);
+            if !keep {
+                measuring.cancel.store(true, Ordering::Relaxed);
+            }
+            keep
+        });
+        self.view.retain_dir_sizes(|dir| !affected(dir));
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
+    use tempfile::tempdir;
+    use crate::file_system::dir_size::DirSize;
+    use crate::file_system::navigator::Navigator;
+    use crate::file_system::operations::OperationManager;
+    use crate::file_system::trash::Trash;
+    use crate::model::pane_controls::PaneControlsEvent;
+    use crate::ui::file_pane::Container;
+
+    /// Handles the pane's events until its listing is complete.
+    async fn settle(container: &mut Container) {
+        for _ in 0..500 {
+            while let Ok(event) = container.receiver.try_recv() {
+                container.handle_navigation_event(event);
+            }
+            if !container.is_loading() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("listing did not finish");
+    }
+
+    async fn run_jobs(container: &mut Container) {
+        let mut manager = OperationManager::new();
+        for (title, operations) in container.take_jobs() {
+            manager.submit(title, operations);
+        }
+        for _ in 0..500 {
+            manager.poll();
+            if !manager.is_busy() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("jobs did not finish");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn emptying_the_trash_leaves_the_directory_it_was_opened_from() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().join("home");
+        fs::create_dir(&home).unwrap();
+        fs::write(home.join("keep"), "keep").unwrap();
+        fs::write(home.join("junk"), "junk").unwrap();
+        let trash = Trash::with_root(dir.path().join("Trash"));
+        trash.trash(home.join("junk")).unwrap();
+
+        let mut container = Container::with_trash(Navigator::new(&home), trash.clone());
+        settle(&mut container).await;
+        container.handle_pane_controls_event(&PaneControlsEvent::OpenTrash, Path::new("/"));
+        settle(&mut container).await;
+        assert!(container.view.items().iter().any(|item| item.is_parent_link() && item.path == home));
+
+        container.handle_pane_controls_event(&PaneControlsEvent::EmptyTrash, Path::new("/"));
+        assert!(container.pending_deletion().unwrap().iter().all(|item| item.trash_info.is_some()));
+        container.confirm_deletion(true);
+        run_jobs(&mut container).await;
+
+        assert_eq!(fs::read_to_string(home.join("keep")).unwrap(), "keep");
+        assert_eq!(fs::read_dir(trash.files_dir()).unwrap().count(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_successful_refresh_clears_the_error_of_a_failed_one() {
+        let dir = tempdir().unwrap();
+        let shown = dir.path().join("shown");
+        fs::create_dir(&shown).unwrap();
+        let mut container = Container::new(Navigator::new(&shown));
+        settle(&mut container).await;
+
+        fs::remove_dir(&shown).unwrap();
+        container.refresh_items();
+        assert!(container.view.error().is_some());
+
+        fs::create_dir(&shown).unwrap();
+        fs::write(shown.join("back"), "").unwrap();
+        container.refresh_items();
+        settle(&mut container).await;
+
+        assert_eq!(container.view.error(), None);
+        assert!(container.view.items().iter().any(|item| item.name == "back"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn refreshing_drops_measured_sizes_of_what_the_folder_holds() {
+        let dir = tempdir().unwrap();
+        let inner = dir.path().join("inner");
+        fs::create_dir(&inner).unwrap();
+        let mut container = Container::new(Navigator::new(dir.path()));
+        settle(&mut container).await;
+        container.view.set_dir_size(inner.clone(), DirSize { bytes: 1, done: true });
+
+        container.refresh_items();
+        settle(&mut container).await;
+
+        assert_eq!(container.view.dir_size(&inner), None);
+    }
+}
@@ -1,4 +1,8 @@
 pub mod view;
 pub mod container;
+pub mod columns;
+mod columns_tests;
+mod container_tests;
+mod view_tests;
 
 pub use container::Container;
\ No newline at end of file
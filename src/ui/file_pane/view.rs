@@ -1,16 +1,27 @@
-use std::collections::BTreeSet;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use egui::text::LayoutJob;
+use egui::text::{CCursor, CCursorRange, LayoutJob};
 use egui::*;
 use tokio::sync::mpsc;
 
+use crate::file_system::dir_size::DirSize;
+use crate::file_system::disk_space::DiskSpace;
+use crate::file_system::navigator::Breadcrumb;
+use crate::file_system::path_input::Completion;
+use crate::file_system::watcher::FileChange;
 use crate::model::*;
+use crate::model::format::{DateFormat, Formats, SizeFormat};
+use crate::model::sort::SortOrder;
+use crate::model::totals::Totals;
+use crate::ui::file_pane::columns::{CellContext, Column, PROVIDERS};
 use crate::ui::keyboard::keyboard;
 
 #[derive(Debug)]
 pub enum NavigatedEvent {
     DirectoryOpened(PathBuf),
+    /// Go to where the link at this path points.
+    LinkFollowed(PathBuf),
     TraversedUp,
     SelectionMoved {
         index: usize,
@@ -18,19 +29,125 @@ pub enum NavigatedEvent {
         additive: bool,
         direction: Option<MoveDirection>,
     },
+    /// The directory has to be listed again.
     FilesUpdated,
+    FilesChanged(Vec<FileChange>),
+    /// A batch of a listing running in the background; `generation` tells
+    /// which one. The batch comes sorted by `sorted_by`.
+    ItemsListed {
+        generation: u64,
+        items: Vec<Item>,
+        sorted_by: SortOrder,
+        done: bool,
+    },
+    RenameRequested,
+    RenameSubmitted {
+        path: PathBuf,
+        new_name: String,
+    },
+    SortChanged(SortOrder),
+    /// Applies to both panes and is saved in the config.
+    FormatsChanged(Formats),
+    /// The columns for the current location.
+    ColumnsChanged(Vec<Column>),
+    /// Measure what these directories hold, crossing into other file
+    /// systems only with `cross_devices`.
+    SizeRequested {
+        paths: Vec<PathBuf>,
+        cross_devices: bool,
+    },
+    /// Text submitted from the path bar.
+    PathEntered(String),
+    /// Tab in the path bar.
+    PathCompletionRequested(String),
+    /// Progress of a measurement; `generation` tells which one.
+    SizeMeasured {
+        generation: u64,
+        path: PathBuf,
+        size: DirSize,
+    },
+}
+
+/// Shown in an editor whose text could not be handed on because the pane
+/// is still busy with earlier events.
+const BUSY: &str = "Busy, try again";
+
+/// Matches of an ambiguous completion listed under the path bar.
+const SHOWN_CANDIDATES: usize = 10;
+
+/// Width of the resize handle after each header.
+const HANDLE_WIDTH: f32 = 6.0;
+const MIN_COLUMN_WIDTH: f32 = 16.0;
+/// Auto-fit looks at this many items at most, so it stays quick in huge
+/// directories.
+const AUTOFIT_ROWS: usize = 5000;
+
+/// Drag-and-drop payload of a header being moved.
+#[derive(Clone, Copy)]
+struct DraggedColumn {
+    /// Address of the view, so headers only move within their pane.
+    view: usize,
+    index: usize,
+}
+
+/// Inline editor state for the Name cell of the item being renamed.
+struct RenameState {
+    path: PathBuf,
+    text: String,
+    error: Option<String>,
+    focus_requested: bool,
+    submitted: bool,
+}
+
+enum RenameAction {
+    Submit,
+    Cancel,
+}
+
+/// The path bar while it is being edited.
+struct PathEdit {
+    text: String,
+    error: Option<String>,
+    /// Matches of the last completion, when there was more than one.
+    candidates: Vec<String>,
+    focus_requested: bool,
+    /// Move the text cursor after the text on the next frame.
+    cursor_to_end: bool,
+    submitted: bool,
+}
+
+enum PathAction {
+    Submit,
+    Complete,
+    Cancel,
 }
 
 pub struct View {
     items: Vec<Item>,
     columns: Vec<Column>,
     sender: mpsc::Sender<NavigatedEvent>,
-    breadcrumbs: Vec<String>,
+    breadcrumbs: Vec<Breadcrumb>,
+    path_edit: Option<PathEdit>,
+    /// Why the last navigation or refresh failed.
+    error: Option<String>,
+    /// Entries listed so far while a listing streams in.
+    loading: Option<usize>,
+    /// Kept across navigation, so each pane remembers its own order.
+    sort: SortOrder,
+    formats: Formats,
+    /// Measured directories by path, kept across navigation until the
+    /// container forgets them.
+    dir_sizes: HashMap<PathBuf, DirSize>,
+    /// Of the file system holding the location, for the status bar.
+    disk_space: Option<DiskSpace>,
+    /// Worked out again once the items, selection or sizes change.
+    totals: Option<Totals>,
 
     selected_indices: BTreeSet<usize>,
     cursor_index: usize,
     selection_anchor: Option<usize>,
     last_direction: Option<MoveDirection>,
+    rename: Option<RenameState>,
 }
 
 impl View {
@@ -38,54 +155,277 @@ impl View {
         items: Vec<Item>,
         columns: Vec<Column>,
         sender: mpsc::Sender<NavigatedEvent>,
-        breadcrumbs: Vec<String>,
+        breadcrumbs: Vec<Breadcrumb>,
     ) -> Self {
         let mut view = Self {
             items,
             columns,
             sender,
             breadcrumbs,
+            path_edit: None,
+            error: None,
+            loading: None,
+            sort: SortOrder::default(),
+            formats: Formats::default(),
+            dir_sizes: HashMap::new(),
+            disk_space: None,
+            totals: None,
             selected_indices: BTreeSet::new(),
             cursor_index: 0,
             selection_anchor: Some(0),
             last_direction: None,
+            rename: None,
         };
+        view.sort.sort(&mut view.items);
         view.move_cursor_to_first();
         view
     }
 
     pub fn ui(&mut self, ui: &mut Ui, focused: bool) {
-        keyboard::handle(self, ui, focused);
+        keyboard::handle(self, ui, focused && !self.is_renaming() && !self.is_editing_path());
+        if self.formats.date == DateFormat::Relative {
+            // keep "3 min ago" current
+            ui.ctx().request_repaint_after(std::time::Duration::from_secs(30));
+        }
 
         let pane_rect = vec2(ui.available_width(), ui.available_height());
 
         ui.allocate_ui(pane_rect, |ui| {
             ui.vertical(|ui| {
-                ui.horizontal(|ui| {
-                    ui.label("📂");
-                    for crumb in &self.breadcrumbs {
-                        ui.label(format!("{} /", crumb));
+                match self.path_edit.take() {
+                    Some(mut edit) => {
+                        let action = Self::draw_path_editor(ui, &mut edit);
+                        self.path_edit = Some(edit);
+                        self.apply_path_action(action);
                     }
-                });
+                    None => self.draw_breadcrumbs(ui),
+                }
+                if let Some(error) = &self.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                if let Some(count) = self.loading {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Loading {} items…", count));
+                    });
+                }
                 ui.separator();
                 self.draw_headers(ui);
                 ui.separator();
                 ui.separator();
 
+                let mut rename = self.rename.take();
+                let mut rename_action = None;
+
+                // only the visible rows are laid out, so a frame costs the
+                // same however many items there are
+                let row_height = Self::row_height(ui);
+                let status_height = row_height + ui.spacing().item_spacing.y;
                 ScrollArea::vertical()
                     .auto_shrink([false, false])
+                    .max_height((ui.available_height() - status_height).max(0.0))
                     .id_salt(self as *const _ as usize)
-                    .show(ui, |ui| {
-                        for (i, item) in self.items.iter().enumerate() {
+                    .show_rows(ui, row_height, self.items.len(), |ui, rows| {
+                        if focused {
+                            self.scroll_to_cursor(ui, rows.start, row_height);
+                        }
+                        for i in rows {
+                            let item = &self.items[i];
                             let selected = self.selected_indices.contains(&i);
                             let is_cursor = self.cursor_index == i;
-                            self.draw_item(ui, item, focused, selected, is_cursor);
+                            let editing = rename.as_mut().filter(|r| r.path == item.path);
+                            if let Some(action) = self.draw_item(ui, item, focused, selected, is_cursor, editing) {
+                                rename_action = Some(action);
+                            }
                         }
                     });
+
+                self.draw_status_bar(ui, row_height);
+
+                self.rename = rename;
+                self.apply_rename_action(rename_action);
             });
         });
     }
 
+    pub fn is_renaming(&self) -> bool {
+        self.rename.is_some()
+    }
+
+    /// Opens the inline editor on the cursor row.
+    pub fn start_rename(&mut self) {
+        let Some(item) = self.get_cursor_item().filter(|item| !item.is_parent_link()) else { return };
+
+        self.rename = Some(RenameState {
+            path: item.path.clone(),
+            text: item.display_name(),
+            error: None,
+            focus_requested: false,
+            submitted: false,
+        });
+    }
+
+    /// Keeps the editor open and shows why the submitted name was rejected.
+    pub fn rename_failed(&mut self, error: String) {
+        if let Some(rename) = self.rename.as_mut() {
+            rename.error = Some(error);
+            rename.submitted = false;
+            rename.focus_requested = false;
+        }
+    }
+
+    pub fn finish_rename(&mut self) {
+        self.rename = None;
+    }
+
+    pub fn is_editing_path(&self) -> bool {
+        self.path_edit.is_some()
+    }
+
+    /// Turns the path bar into an editor holding the current path.
+    pub fn start_path_edit(&mut self) {
+        let Some(crumb) = self.breadcrumbs.last() else { return };
+        let mut text = file_name::escape(crumb.path.as_os_str());
+        if !text.ends_with('/') {
+            text.push('/');
+        }
+
+        self.path_edit = Some(PathEdit {
+            text,
+            error: None,
+            candidates: Vec::new(),
+            focus_requested: false,
+            cursor_to_end: true,
+            submitted: false,
+        });
+    }
+
+    /// Keeps the editor open and shows why the path was rejected.
+    pub fn path_edit_failed(&mut self, error: String) {
+        if let Some(edit) = self.path_edit.as_mut() {
+            edit.error = Some(error);
+            edit.submitted = false;
+            edit.focus_requested = false;
+        }
+    }
+
+    pub fn finish_path_edit(&mut self) {
+        self.path_edit = None;
+    }
+
+    /// Applies a completion of `input`, unless the text changed meanwhile.
+    pub fn path_completed(&mut self, input: &str, completion: Completion) {
+        let Some(edit) = self.path_edit.as_mut().filter(|edit| edit.text == input) else { return };
+        edit.cursor_to_end = edit.text != completion.text;
+        edit.text = completion.text;
+        edit.candidates = completion.candidates;
+    }
+
+    fn apply_path_action(&mut self, action: Option<PathAction>) {
+        let Some(edit) = self.path_edit.as_mut() else { return };
+        match action {
+            Some(PathAction::Submit) => {
+                match self.sender.try_send(NavigatedEvent::PathEntered(edit.text.clone())) {
+                    Ok(()) => edit.submitted = true,
+                    Err(_) => self.path_edit_failed(BUSY.into()),
+                }
+            }
+            Some(PathAction::Complete) => {
+                match self.sender.try_send(NavigatedEvent::PathCompletionRequested(edit.text.clone())) {
+                    Ok(()) => {}
+                    Err(_) => self.path_edit_failed(BUSY.into()),
+                }
+            }
+            Some(PathAction::Cancel) => self.path_edit = None,
+            None => {}
+        }
+    }
+
+    /// Each ancestor opens on click; the folder icon starts editing.
+    fn draw_breadcrumbs(&mut self, ui: &mut Ui) {
+        let mut edit = false;
+        ui.horizontal(|ui| {
+            edit = ui.add(Button::new("📂").frame(false)).on_hover_text("Edit the path (Ctrl+L)").clicked();
+            let last = self.breadcrumbs.len().saturating_sub(1);
+            for (i, crumb) in self.breadcrumbs.iter().enumerate() {
+                if i == last {
+                    ui.strong(&crumb.name);
+                } else if ui.add(Button::new(&crumb.name).frame(false)).clicked() {
+                    let _ = self.sender.try_send(NavigatedEvent::DirectoryOpened(crumb.path.clone()));
+                }
+                if crumb.name != "/" {
+                    ui.label("/");
+                }
+            }
+        });
+        if edit {
+            self.start_path_edit();
+        }
+    }
+
+    fn draw_path_editor(ui: &mut Ui, edit: &mut PathEdit) -> Option<PathAction> {
+        let id = ui.make_persistent_id("path_edit");
+        let mut output = TextEdit::singleline(&mut edit.text)
+            .id(id)
+            // Tab completes instead of moving on
+            .lock_focus(true)
+            .desired_width(f32::INFINITY)
+            .show(ui);
+        let response = &output.response;
+
+        if !edit.focus_requested {
+            response.request_focus();
+            edit.focus_requested = true;
+        }
+        if std::mem::take(&mut edit.cursor_to_end) {
+            let end = CCursor::new(edit.text.chars().count());
+            output.state.cursor.set_char_range(Some(CCursorRange::one(end)));
+            output.state.store(ui.ctx(), id);
+        }
+        if response.changed() {
+            edit.error = None;
+            edit.candidates.clear();
+        }
+        if let Some(error) = &edit.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        if !edit.candidates.is_empty() {
+            let mut shown = edit.candidates.iter().take(SHOWN_CANDIDATES).cloned().collect::<Vec<_>>().join("  ");
+            if edit.candidates.len() > SHOWN_CANDIDATES {
+                shown.push_str(&format!("  … {} more", edit.candidates.len() - SHOWN_CANDIDATES));
+            }
+            ui.weak(shown);
+        }
+
+        if response.has_focus() && ui.input(|i| i.key_pressed(Key::Tab)) {
+            Some(PathAction::Complete)
+        } else if edit.submitted || !response.lost_focus() {
+            None
+        } else if ui.input(|i| i.key_pressed(Key::Enter)) {
+            Some(PathAction::Submit)
+        } else {
+            Some(PathAction::Cancel)
+        }
+    }
+
+    fn apply_rename_action(&mut self, action: Option<RenameAction>) {
+        match action {
+            Some(RenameAction::Submit) => {
+                let Some(rename) = self.rename.as_mut() else { return };
+                let submitted = self.sender.try_send(NavigatedEvent::RenameSubmitted {
+                    path: rename.path.clone(),
+                    new_name: rename.text.clone(),
+                });
+                match submitted {
+                    Ok(()) => rename.submitted = true,
+                    Err(_) => self.rename_failed(BUSY.into()),
+                }
+            }
+            Some(RenameAction::Cancel) => self.rename = None,
+            None => {}
+        }
+    }
+
     pub fn handle_selection_moved(
         &mut self,
         index: usize,
@@ -109,9 +449,116 @@ impl View {
         }
     }
 
-    pub fn update_contents(&mut self, items: Vec<Item>, breadcrumbs: Vec<String>) {
-        self.items = items;
+    /// Replaces the items. The cursor and selection follow their entries to
+    /// wherever they now sit; selected entries that are gone are dropped.
+    pub fn update_contents(&mut self, mut items: Vec<Item>, breadcrumbs: Vec<Breadcrumb>) {
+        self.sort.sort(&mut items);
         self.breadcrumbs = breadcrumbs;
+        self.keeping_selection(|view| view.items = items);
+    }
+
+    /// Drops the entries at `removed` and adds or replaces `updated` ones,
+    /// keeping the sort order, cursor and selection.
+    pub fn patch_contents(&mut self, removed: &[PathBuf], mut updated: Vec<Item>) {
+        let replaced: HashSet<PathBuf> = removed.iter().cloned()
+            .chain(updated.iter().map(|item| item.path.clone()))
+            .collect();
+        self.sort.sort(&mut updated);
+        self.keeping_selection(|view| {
+            view.items.retain(|item| item.is_parent_link() || !replaced.contains(&item.path));
+            view.sort.merge(&mut view.items, updated);
+        });
+    }
+
+    /// Adds new entries sorted by `sorted_by`, merging them in when that is
+    /// still the pane's order.
+    pub fn add_contents(&mut self, mut items: Vec<Item>, sorted_by: SortOrder) {
+        if sorted_by != self.sort {
+            self.sort.sort(&mut items);
+        }
+        self.keeping_selection(|view| view.sort.merge(&mut view.items, items));
+    }
+
+    pub fn sort_order(&self) -> SortOrder {
+        self.sort
+    }
+
+    /// Re-sorts the items, keeping the cursor and selection on the same entries.
+    pub fn set_sort(&mut self, sort: SortOrder) {
+        self.sort = sort;
+        self.keeping_selection(|view| view.sort.sort(&mut view.items));
+    }
+
+    /// Runs `change` on the items and then finds the cursor, anchor and
+    /// selection again by path. If the cursor entry is gone, the cursor
+    /// keeps its position, clamped to the new length.
+    fn keeping_selection(&mut self, change: impl FnOnce(&mut Self)) {
+        let path_at = |view: &Self, index: usize| view.items.get(index).map(|item| item.path.clone());
+        let cursor = path_at(self, self.cursor_index);
+        let anchor = self.selection_anchor.and_then(|index| path_at(self, index));
+        let selected: Vec<PathBuf> = self.get_selected_items().into_iter().map(|item| item.path).collect();
+
+        change(self);
+
+        // one pass over the items instead of a search per selected path
+        let indices: HashMap<&Path, usize> = self.items.iter()
+            .enumerate()
+            .map(|(index, item)| (item.path.as_path(), index))
+            .collect();
+        let index_of = |path: &PathBuf| indices.get(path.as_path()).copied();
+        let selected_indices = selected.iter().filter_map(index_of).collect();
+        let cursor_index = cursor.as_ref().and_then(index_of)
+            .unwrap_or(self.cursor_index.min(self.items.len().saturating_sub(1)));
+        let selection_anchor = anchor.as_ref().and_then(index_of).or(Some(cursor_index));
+
+        self.selected_indices = selected_indices;
+        self.cursor_index = cursor_index;
+        self.selection_anchor = selection_anchor;
+        self.totals = None;
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    #[cfg(test)]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
+    }
+
+    pub fn set_dir_size(&mut self, path: PathBuf, size: DirSize) {
+        self.dir_sizes.insert(path, size);
+        self.totals = None;
+    }
+
+    pub fn dir_size(&self, path: &Path) -> Option<DirSize> {
+        self.dir_sizes.get(path).copied()
+    }
+
+    /// Keeps the sizes of the directories `keep` returns true for.
+    pub fn retain_dir_sizes(&mut self, mut keep: impl FnMut(&Path) -> bool) {
+        self.dir_sizes.retain(|path, _| keep(path));
+        self.totals = None;
+    }
+
+    pub fn set_disk_space(&mut self, space: Option<DiskSpace>) {
+        self.disk_space = space;
+    }
+
+    pub fn set_loading(&mut self, loading: Option<usize>) {
+        self.loading = loading;
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<Column>) {
+        self.columns = columns;
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
     }
 
     pub fn get_selected_items(&self) -> Vec<Item> {
@@ -124,6 +571,10 @@ impl View {
         &self.selected_indices
     }
 
+    pub fn index_of(&self, path: &Path) -> Option<usize> {
+        self.items.iter().position(|item| item.path == path)
+    }
+
     pub fn get_cursor_item(&self) -> Option<&Item> {
         self.items.get(self.cursor_index)
     }
@@ -149,7 +600,7 @@ impl View {
 
     pub fn select_single(&mut self, index: usize) {
         self.selected_indices.clear();
-        self.selected_indices.insert(index);
+        self.select(index);
         self.cursor_index = index;
         self.selection_anchor = Some(index);
     }
@@ -158,7 +609,7 @@ impl View {
         let (start, end) = if anchor <= index { (anchor, index) } else { (index, anchor) };
         self.selected_indices.clear();
         for i in start..=end {
-            self.selected_indices.insert(i);
+            self.select(i);
         }
         self.cursor_index = index;
         self.selection_anchor = Some(anchor);
@@ -167,12 +618,20 @@ impl View {
     fn add_range_to_selection(&mut self, anchor: usize, index: usize) {
         let (start, end) = if anchor <= index { (anchor, index) } else { (index, anchor) };
         for i in start..=end {
-            self.selected_indices.insert(i);
+            self.select(i);
         }
         self.cursor_index = index;
         self.selection_anchor = Some(anchor);
     }
 
+    /// The ".." row can hold the cursor but is never part of the selection.
+    fn select(&mut self, index: usize) {
+        self.totals = None;
+        if !self.items.get(index).is_some_and(Item::is_parent_link) {
+            self.selected_indices.insert(index);
+        }
+    }
+
     fn reset_anchor_if_diff_dir(&mut self, direction: Option<MoveDirection>, prev_dir: Option<MoveDirection>) {
         if prev_dir != direction {
             self.selection_anchor = Some(self.cursor_index);
@@ -185,20 +644,205 @@ impl View {
         count.max(1)
     }
 
-    fn draw_headers(&self, ui: &mut Ui) {
+    /// Draws the header row. Headers can be dragged onto another one to
+    /// take its place; the handles between them resize on drag and fit the
+    /// column to its content on double-click.
+    fn draw_headers(&mut self, ui: &mut Ui) {
+        let height = Self::row_height(ui);
+        let view = self as *const _ as usize;
+        let mut changed = false;
+        let mut moved = None;
+
         ui.horizontal(|ui| {
-            for col in &self.columns {
-                ui.allocate_ui(vec2(col.width, ui.available_height()), |ui| {
-                    ui.label(&col.name);
-                });
+            for index in 0..self.columns.len() {
+                let col = self.columns[index];
+                let (rect, response) = Self::cell(ui, col.width, height, |ui| self.draw_header(ui, &col));
+
+                response.dnd_set_drag_payload(DraggedColumn { view, index });
+                if response.dnd_hover_payload::<DraggedColumn>().is_some_and(|dragged| dragged.view == view) {
+                    ui.painter().vline(rect.left(), rect.y_range(), ui.visuals().selection.stroke);
+                }
+                if let Some(dragged) = response.dnd_release_payload::<DraggedColumn>()
+                    && dragged.view == view {
+                    moved = Some((dragged.index, index));
+                }
+
+                let (handle_rect, handle) = ui.allocate_exact_size(vec2(HANDLE_WIDTH, height), Sense::click_and_drag());
+                let active = handle.hovered() || handle.dragged();
+                let stroke = if active {
+                    ui.visuals().widgets.active.fg_stroke
+                } else {
+                    ui.visuals().widgets.noninteractive.bg_stroke
+                };
+                ui.painter().vline(handle_rect.center().x, handle_rect.y_range(), stroke);
+                if active {
+                    ui.ctx().set_cursor_icon(CursorIcon::ResizeColumn);
+                }
+                if handle.dragged() {
+                    let width = self.columns[index].width + handle.drag_delta().x;
+                    self.columns[index].width = width.max(MIN_COLUMN_WIDTH);
+                }
+                if handle.double_clicked() {
+                    self.columns[index].width = self.fit_width(ui, &col);
+                }
+                changed |= handle.drag_stopped() || handle.double_clicked();
             }
         });
+
+        if let Some((from, to)) = moved.filter(|(from, to)| from != to) {
+            let col = self.columns.remove(from);
+            self.columns.insert(to, col);
+            changed = true;
+        }
+        if changed {
+            let _ = self.sender.try_send(NavigatedEvent::ColumnsChanged(self.columns.clone()));
+        }
     }
 
-    fn draw_item(&self, ui: &mut Ui, item: &Item, pane_focused: bool, selected: bool, is_cursor: bool) {
-        let row_start = ui.cursor().min;
-        let row_end = row_start + vec2(ui.max_rect().max.x, Self::row_height(ui));
-        let row_rect = Rect::from_min_max(row_start, row_end);
+    fn draw_header(&self, ui: &mut Ui, col: &Column) -> Response {
+        let provider = col.provider;
+        let response = match provider.sort_key() {
+            Some(key) => {
+                let response = ui.add(Button::new(self.header_title(col)).frame(false).sense(Sense::click_and_drag()))
+                    .on_hover_text("Click to sort, drag to move, right-click for options");
+                if response.clicked() {
+                    self.send_sort(self.sort.toggled(key));
+                }
+                response
+            }
+            None => ui.add(Label::new(provider.title()).sense(Sense::click_and_drag()))
+                .on_hover_text("Drag to move, right-click for options"),
+        };
+
+        response.context_menu(|ui| {
+            if provider.sort_key().is_some() {
+                let mut sort = self.sort;
+                ui.checkbox(&mut sort.case_sensitive, "Case-sensitive names");
+                ui.checkbox(&mut sort.dirs_first, "Directories first");
+                if sort != self.sort {
+                    self.send_sort(sort);
+                }
+                ui.separator();
+            }
+            self.format_menu(ui, provider.id());
+            self.columns_menu(ui);
+        });
+        response
+    }
+
+    /// The column's title, with an arrow if the pane is sorted by it.
+    fn header_title(&self, col: &Column) -> String {
+        let title = col.provider.title();
+        match col.provider.sort_key() {
+            Some(key) if key == self.sort.key && self.sort.descending => format!("{} ⏷", title),
+            Some(key) if key == self.sort.key => format!("{} ⏶", title),
+            _ => title.to_string(),
+        }
+    }
+
+    /// Width that fits the header and the cells of the first
+    /// `AUTOFIT_ROWS` items.
+    fn fit_width(&self, ui: &Ui, col: &Column) -> f32 {
+        let font = TextStyle::Body.resolve(ui.style());
+        let cx = self.cell_context();
+        let texts = self.items.iter()
+            .take(AUTOFIT_ROWS)
+            .map(|item| col.provider.text(item, &cx))
+            .chain(std::iter::once(self.header_title(col)));
+
+        let widest = ui.fonts(|fonts| {
+            texts.map(|text| fonts.layout_no_wrap(text, font.clone(), Color32::WHITE).size().x)
+                .fold(0.0, f32::max)
+        });
+        (widest + 2.0 * ui.spacing().button_padding.x).max(MIN_COLUMN_WIDTH)
+    }
+
+    /// Lays out a cell exactly `width` wide, clipping what does not fit, so
+    /// that headers and rows line up.
+    fn cell<R>(ui: &mut Ui, width: f32, height: f32, add_contents: impl FnOnce(&mut Ui) -> R) -> (Rect, R) {
+        let (rect, _) = ui.allocate_exact_size(vec2(width, height), Sense::hover());
+        let mut cell = ui.new_child(UiBuilder::new().max_rect(rect).layout(Layout::left_to_right(Align::Center)));
+        cell.set_clip_rect(rect.intersect(ui.clip_rect()));
+        (rect, add_contents(&mut cell))
+    }
+
+    /// Format choices for size and date columns. Custom date patterns can
+    /// only be set in the config file.
+    fn format_menu(&self, ui: &mut Ui, column: &str) {
+        let mut formats = self.formats.clone();
+        match column {
+            "size" => {
+                for size in SizeFormat::ALL {
+                    ui.radio_value(&mut formats.size, size, size.label());
+                }
+                ui.separator();
+            }
+            "modified" | "created" | "accessed" | "deleted" => {
+                let custom = matches!(formats.date, DateFormat::Custom(_)).then(|| formats.date.clone());
+                for date in DateFormat::PRESETS.into_iter().chain(custom) {
+                    let label = date.label();
+                    ui.radio_value(&mut formats.date, date, label);
+                }
+                ui.separator();
+            }
+            _ => {}
+        }
+        if formats != self.formats {
+            let _ = self.sender.try_send(NavigatedEvent::FormatsChanged(formats));
+        }
+    }
+
+    /// Shows or hides columns; shown ones are added at the end. The Name
+    /// column always stays.
+    fn columns_menu(&self, ui: &mut Ui) {
+        ui.menu_button("Columns", |ui| {
+            for &provider in PROVIDERS {
+                let shown = self.columns.iter().position(|col| col.id() == provider.id());
+                let mut show = shown.is_some();
+                let checkbox = Checkbox::new(&mut show, provider.title());
+                if !ui.add_enabled(provider.id() != "name", checkbox).changed() {
+                    continue;
+                }
+
+                let mut columns = self.columns.clone();
+                match shown {
+                    Some(index) => {
+                        columns.remove(index);
+                    }
+                    None => columns.push(Column { provider, width: provider.default_width() }),
+                }
+                let _ = self.sender.try_send(NavigatedEvent::ColumnsChanged(columns));
+            }
+        });
+    }
+
+    fn send_sort(&self, sort: SortOrder) {
+        let _ = self.sender.try_send(NavigatedEvent::SortChanged(sort));
+    }
+
+    /// Keeps the cursor row in view, whether or not it is among the drawn
+    /// `first_row..` ones.
+    fn scroll_to_cursor(&self, ui: &Ui, first_row: usize, row_height: f32) {
+        let row_step = row_height + ui.spacing().item_spacing.y;
+        let top = ui.cursor().min.y + (self.cursor_index as f32 - first_row as f32) * row_step;
+        let cursor_rect = Rect::from_x_y_ranges(ui.max_rect().x_range(), top..=top + row_height);
+        ui.scroll_to_rect(cursor_rect, None);
+    }
+
+    fn draw_item(
+        &self,
+        ui: &mut Ui,
+        item: &Item,
+        pane_focused: bool,
+        selected: bool,
+        is_cursor: bool,
+        mut rename: Option<&mut RenameState>,
+    ) -> Option<RenameAction> {
+        let mut rename_action = None;
+
+        // exactly one row high, as the scroll area assumes
+        let row_height = Self::row_height(ui);
+        let (row_rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), row_height), Sense::hover());
 
         if pane_focused && is_cursor {
             ui.painter().rect_stroke(
@@ -207,28 +851,90 @@ impl View {
                 Stroke::new(2.0, Color32::LIGHT_BLUE),
                 StrokeKind::Inside,
             );
-            ui.scroll_to_rect(row_rect, None);
         }
 
-        ui.horizontal(|ui| {
-            for col in &self.columns {
-                ui.allocate_ui(vec2(col.width, Self::row_height(ui)), |ui| {
-                    self.draw_item_cell(ui, item, &col.name, selected);
+        let mut row = ui.new_child(
+            UiBuilder::new()
+                .id_salt(&item.path)
+                .max_rect(row_rect)
+                .layout(Layout::left_to_right(Align::Center)),
+        );
+        for col in &self.columns {
+            Self::cell(&mut row, col.width, row_height, |ui| {
+                match rename.as_deref_mut() {
+                    Some(rename) if col.id() == "name" => {
+                        rename_action = Self::draw_rename_editor(ui, rename, col.width);
+                    }
+                    _ => self.draw_item_cell(ui, item, col, selected),
+                }
+            });
+            // keeps cells under their headers, which are followed by a handle
+            row.allocate_exact_size(vec2(HANDLE_WIDTH, row_height), Sense::hover());
+        }
+
+        rename_action
+    }
+
+    fn draw_rename_editor(ui: &mut Ui, rename: &mut RenameState, width: f32) -> Option<RenameAction> {
+        let response = ui.add(TextEdit::singleline(&mut rename.text).desired_width(width));
+
+        if !rename.focus_requested {
+            response.request_focus();
+            rename.focus_requested = true;
+        }
+        if response.changed() {
+            rename.error = None;
+        }
+        if let Some(error) = &rename.error {
+            response.show_tooltip_text(error);
+        }
+
+        if rename.submitted || !response.lost_focus() {
+            None
+        } else if ui.input(|i| i.key_pressed(Key::Enter)) {
+            Some(RenameAction::Submit)
+        } else {
+            Some(RenameAction::Cancel)
+        }
+    }
+
+    /// What is selected out of what is listed, and the space left where the
+    /// pane is.
+    fn draw_status_bar(&mut self, ui: &mut Ui, height: f32) {
+        let totals = *self.totals.get_or_insert_with(|| {
+            Totals::of(&self.items, &self.selected_indices, |item| {
+                self.dir_sizes.get(&item.path).map(|size| size.bytes)
+            })
+        });
+        let size = |bytes| self.formats.size.format(bytes);
+
+        Self::cell(ui, ui.available_width(), height, |ui| {
+            ui.label(format!(
+                "{} of {} files, {} of {} selected, {} of {} folders",
+                totals.selected_files,
+                totals.files,
+                size(totals.selected_bytes),
+                size(totals.bytes),
+                totals.selected_dirs,
+                totals.dirs,
+            ));
+            if let Some(space) = self.disk_space {
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    ui.weak(format!("{} free of {}", size(space.free), size(space.total)));
                 });
             }
         });
     }
 
-    fn draw_item_cell(&self, ui: &mut Ui, item: &Item, col_name: &str, selected_text: bool) {
-        let content = match col_name {
-            "Icon" => match item.item_type {
-                ItemType::File => "📄".to_string(),
-                ItemType::Directory => "📁".to_string(),
-            },
-            "Name" => item.name.clone(),
-            "Size" => format!("{} bytes", item.size),
-            "Modified" => item.modified.to_rfc2822(),
-            _ => String::new(),
+    fn cell_context(&self) -> CellContext<'_> {
+        CellContext { formats: &self.formats, dir_sizes: &self.dir_sizes, now: chrono::Local::now() }
+    }
+
+    fn draw_item_cell(&self, ui: &mut Ui, item: &Item, col: &Column, selected_text: bool) {
+        let content = if item.is_parent_link() && !matches!(col.id(), "icon" | "name") {
+            String::new()
+        } else {
+            col.provider.text(item, &self.cell_context())
         };
 
         let mut text_color = ui.visuals().text_color();
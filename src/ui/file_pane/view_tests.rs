@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use egui::{vec2, CentralPanel, Context, Pos2, RawInput, Rect};
+    use tokio::sync::mpsc;
+    use crate::model::{Item, ItemType};
+    use crate::model::sort::SortKey;
+    use crate::ui::file_pane::columns::Column;
+    use crate::ui::file_pane::view::View;
+
+    fn items(names: &[&str]) -> Vec<Item> {
+        names.iter()
+            .map(|name| Item {
+                name: name.into(),
+                path: Path::new("/d").join(name),
+                item_type: ItemType::File,
+                size: Some(0),
+                modified: None,
+                trash_info: None,
+                unix: None,
+            })
+            .collect()
+    }
+
+    fn view(names: &[&str]) -> View {
+        let (tx, _rx) = mpsc::channel(1);
+        View::new(items(names), Vec::new(), tx, Vec::new())
+    }
+
+    fn cursor_name(view: &View) -> String {
+        view.get_cursor_item().unwrap().display_name()
+    }
+
+    fn selected_names(view: &View) -> Vec<String> {
+        view.get_selected_items().iter().map(Item::display_name).collect()
+    }
+
+    #[test]
+    fn insertion_before_cursor_keeps_cursor_and_selection_on_their_entries() {
+        let mut view = view(&["b", "c", "d"]);
+        view.select_single(1);
+        view.handle_selection_moved(2, true, false, None);
+
+        view.update_contents(items(&["a", "b", "c", "d"]), Vec::new());
+
+        assert_eq!(cursor_name(&view), "d");
+        assert_eq!(view.cursor_index(), 3);
+        assert_eq!(selected_names(&view), ["c", "d"]);
+    }
+
+    #[test]
+    fn removal_before_cursor_keeps_cursor_and_selection_on_their_entries() {
+        let mut view = view(&["a", "b", "c", "d"]);
+        view.select_single(2);
+        view.handle_selection_moved(3, true, true, None);
+
+        view.update_contents(items(&["b", "c", "d"]), Vec::new());
+
+        assert_eq!(cursor_name(&view), "d");
+        assert_eq!(selected_names(&view), ["c", "d"]);
+    }
+
+    #[test]
+    fn removed_entries_leave_the_selection() {
+        let mut view = view(&["a", "b", "c"]);
+        view.select_single(0);
+        view.handle_selection_moved(2, true, false, None);
+
+        view.update_contents(items(&["a", "c"]), Vec::new());
+
+        assert_eq!(selected_names(&view), ["a", "c"]);
+    }
+
+    #[test]
+    fn cursor_stays_in_place_when_its_entry_is_removed() {
+        let mut view = view(&["a", "b", "c"]);
+        view.select_single(1);
+
+        view.update_contents(items(&["a", "c"]), Vec::new());
+        assert_eq!(cursor_name(&view), "c");
+        assert!(view.get_selected_items().is_empty());
+
+        view.update_contents(items(&["a"]), Vec::new());
+        assert_eq!(cursor_name(&view), "a");
+    }
+
+    #[test]
+    fn patching_keeps_order_cursor_and_selection() {
+        let mut view = view(&["b", "d", "f"]);
+        view.select_single(1);
+
+        view.patch_contents(&["/d/b".into()], items(&["a", "e"]));
+
+        let names: Vec<String> = view.items().iter().map(Item::display_name).collect();
+        assert_eq!(names, ["a", "d", "e", "f"]);
+        assert_eq!(cursor_name(&view), "d");
+        assert_eq!(selected_names(&view), ["d"]);
+    }
+
+    #[test]
+    fn listed_batches_are_merged_in_the_current_order() {
+        let mut view = view(&["b", "d"]);
+        view.select_single(1);
+        let old_order = view.sort_order().toggled(SortKey::Name);
+
+        view.add_contents(items(&["e", "c", "a"]), old_order);
+
+        let names: Vec<String> = view.items().iter().map(Item::display_name).collect();
+        assert_eq!(names, ["a", "b", "c", "d", "e"]);
+        assert_eq!(cursor_name(&view), "d");
+    }
+
+    /// Shapes painted by a frame of a pane holding `count` items, after
+    /// a first frame to settle the layout.
+    fn shapes_painted(count: usize) -> usize {
+        let names: Vec<String> = (0..count).map(|i| format!("file{i}")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let (tx, _rx) = mpsc::channel(1);
+        let columns = ["icon", "name", "size", "modified"].iter()
+            .filter_map(|id| Column::new(id))
+            .collect();
+        let mut view = View::new(items(&names), columns, tx, Vec::new());
+
+        let ctx = Context::default();
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0))),
+            ..Default::default()
+        };
+        let mut run = || ctx.run(input.clone(), |ctx| {
+            CentralPanel::default().show(ctx, |ui| view.ui(ui, true));
+        });
+        run();
+        run().shapes.len()
+    }
+
+    #[test]
+    fn frame_cost_does_not_grow_with_item_count() {
+        // both fill the pane, so only the rows that fit should be laid out
+        assert_eq!(shapes_painted(1_000), shapes_painted(100_000));
+    }
+}
@@ -1,6 +1,8 @@
+use std::path::PathBuf;
 use egui::{Key, Ui};
 
-use crate::model::{ItemType, MoveDirection};
+use crate::model::{Item, ItemType, MoveDirection};
+use crate::model::sort::SortKey;
 use crate::ui::file_pane::view::{View, NavigatedEvent};
 
 pub fn handle(view: &mut View, ui: &mut Ui, focused: bool) {
@@ -13,7 +15,78 @@ pub fn handle(view: &mut View, ui: &mut Ui, focused: bool) {
         | handle_arrow_up(view, ui)
         | handle_arrow_down(view, ui)
         | handle_page_up(view, ui)
-        | handle_page_down(view, ui);
+        | handle_page_down(view, ui)
+        | handle_rename(view, ui)
+        | handle_sort(view, ui)
+        | handle_dir_size(view, ui)
+        | handle_path_edit(view, ui);
+}
+
+fn handle_path_edit(view: &mut View, ui: &mut Ui) -> bool {
+    if ui.input(|i| i.modifiers.ctrl && i.key_pressed(Key::L)) {
+        view.start_path_edit();
+        true
+    } else {
+        false
+    }
+}
+
+/// Space measures the directory under the cursor; Alt+Shift+Enter the
+/// selected ones, or all of them when at most one item is selected. With
+/// Ctrl, the measurement goes into other file systems too.
+fn handle_dir_size(view: &mut View, ui: &mut Ui) -> bool {
+    let (cursor, all, ctrl) = ui.input(|i| (
+        i.key_pressed(Key::Space) && !i.modifiers.alt && !i.modifiers.shift,
+        i.key_pressed(Key::Enter) && i.modifiers.alt && i.modifiers.shift,
+        i.modifiers.ctrl,
+    ));
+
+    let measured = |item: &&Item| item.item_type == ItemType::Directory && !item.is_parent_link();
+    let paths: Vec<PathBuf> = if cursor {
+        view.get_cursor_item().filter(measured).map(|item| item.path.clone()).into_iter().collect()
+    } else if all && view.get_selected_indices().len() > 1 {
+        view.get_selected_items().iter().filter(measured).map(|item| item.path.clone()).collect()
+    } else if all {
+        view.items().iter().filter(measured).map(|item| item.path.clone()).collect()
+    } else {
+        return false;
+    };
+
+    if !paths.is_empty() {
+        let _ = view.sender().try_send(NavigatedEvent::SizeRequested { paths, cross_devices: ctrl });
+    }
+    true
+}
+
+fn handle_sort(view: &mut View, ui: &mut Ui) -> bool {
+    let key = ui.input(|i| {
+        if !i.modifiers.ctrl {
+            None
+        } else if i.key_pressed(Key::F3) {
+            Some(SortKey::Name)
+        } else if i.key_pressed(Key::F4) {
+            Some(SortKey::Extension)
+        } else if i.key_pressed(Key::F5) {
+            Some(SortKey::Modified)
+        } else if i.key_pressed(Key::F6) {
+            Some(SortKey::Size)
+        } else {
+            None
+        }
+    });
+
+    let Some(key) = key else { return false };
+    let _ = view.sender().try_send(NavigatedEvent::SortChanged(view.sort_order().toggled(key)));
+    true
+}
+
+fn handle_rename(view: &mut View, ui: &mut Ui) -> bool {
+    if ui.input(|i| i.modifiers.shift && i.key_pressed(Key::F6)) {
+        let _ = view.sender().try_send(NavigatedEvent::RenameRequested);
+        true
+    } else {
+        false
+    }
 }
 
 fn handle_backspace(view: &mut View, ui: &mut Ui) -> bool {
@@ -26,10 +99,23 @@ fn handle_backspace(view: &mut View, ui: &mut Ui) -> bool {
 }
 
 fn handle_enter(view: &mut View, ui: &mut Ui) -> bool {
-    if ui.input(|i| i.key_pressed(Key::Enter)) {
+    if ui.input(|i| i.modifiers.alt) {
+        false
+    } else if ui.input(|i| i.modifiers.ctrl && i.key_pressed(Key::Enter)) {
         if let Some(item) = view.get_cursor_item()
-            .filter(|it| it.item_type == ItemType::Directory) {
-            let _ = view.sender().try_send(NavigatedEvent::DirectoryOpened(item.path.clone()));
+            .filter(|it| matches!(it.item_type, ItemType::Symlink { .. })) {
+            let _ = view.sender().try_send(NavigatedEvent::LinkFollowed(item.path.clone()));
+        }
+        true
+    } else if ui.input(|i| i.key_pressed(Key::Enter)) {
+        match view.get_cursor_item() {
+            Some(item) if item.is_parent_link() => {
+                let _ = view.sender().try_send(NavigatedEvent::TraversedUp);
+            }
+            Some(item) if item.opens_as_directory() => {
+                let _ = view.sender().try_send(NavigatedEvent::DirectoryOpened(item.path.clone()));
+            }
+            _ => {}
         }
         true
     } else {
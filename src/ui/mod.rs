@@ -1,4 +1,8 @@
 pub mod file_pane;
 pub mod double_pane;
 pub mod pane_controls;
-pub mod keyboard;
\ No newline at end of file
+pub mod keyboard;
+pub mod conflict_dialog;
+pub mod confirm_dialog;
+pub mod prompt_dialog;
+pub mod multi_rename;
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod multi_rename_dialog;
\ No newline at end of file
@@ -0,0 +1,148 @@
+use egui::*;
+use crate::file_system::multi_rename::{CaseTransform, MultiRename, PlannedRename, RenameSpec};
+use crate::model::Item;
+
+pub enum MultiRenameResult {
+    Apply(Vec<PlannedRename>),
+    Cancel,
+}
+
+/// Total Commander style batch rename of the selected items with a live
+/// old -> new preview.
+pub struct MultiRenameDialog {
+    items: Vec<Item>,
+    existing: Vec<String>,
+    spec: RenameSpec,
+    apply_error: Option<String>,
+}
+
+impl MultiRenameDialog {
+    /// `existing` are all names in the directory, for collision checks.
+    pub fn new(items: Vec<Item>, existing: Vec<String>) -> Self {
+        MultiRenameDialog { items, existing, spec: RenameSpec::default(), apply_error: None }
+    }
+
+    pub fn apply_failed(&mut self, error: String) {
+        self.apply_error = Some(error);
+    }
+
+    pub fn ui(&mut self, ctx: &Context) -> Option<MultiRenameResult> {
+        let plan = MultiRename::plan(&self.spec, &self.items, &self.existing);
+        let mut result = None;
+
+        let modal = Modal::new(Id::new("multi_rename_dialog")).show(ctx, |ui| {
+            ui.set_min_width(560.0);
+            ui.heading(format!("Multi-rename {} items", self.items.len()));
+            ui.separator();
+
+            if self.draw_spec(ui) {
+                self.apply_error = None;
+            }
+
+            ui.separator();
+            match &plan {
+                Ok(plan) => Self::draw_preview(ui, plan),
+                Err(error) => {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+            }
+            if let Some(error) = &self.apply_error {
+                ui.colored_label(ui.visuals().error_fg_color, format!("Nothing renamed: {}", error));
+            }
+            ui.separator();
+
+            let applicable = plan.as_ref().is_ok_and(|plan| plan.iter().all(|p| p.problem.is_none()));
+            ui.horizontal(|ui| {
+                if ui.add_enabled(applicable, Button::new("Rename")).clicked()
+                    && let Ok(plan) = &plan
+                {
+                    result = Some(MultiRenameResult::Apply(plan.clone()));
+                }
+                if ui.button("Cancel").clicked() {
+                    result = Some(MultiRenameResult::Cancel);
+                }
+            });
+        });
+
+        if result.is_none() && modal.should_close() {
+            result = Some(MultiRenameResult::Cancel);
+        }
+        result
+    }
+
+    /// Returns whether any field was edited this frame.
+    fn draw_spec(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        Grid::new("multi_rename_spec").num_columns(2).show(ui, |ui| {
+            ui.label("Name");
+            changed |= ui.text_edit_singleline(&mut self.spec.name_template).changed();
+            ui.end_row();
+
+            ui.label("Extension");
+            changed |= ui.text_edit_singleline(&mut self.spec.ext_template).changed();
+            ui.end_row();
+
+            ui.label("Search for");
+            ui.horizontal(|ui| {
+                changed |= ui.text_edit_singleline(&mut self.spec.search).changed();
+                changed |= ui.checkbox(&mut self.spec.use_regex, "Regex").changed();
+            });
+            ui.end_row();
+
+            ui.label("Replace with");
+            changed |= ui.text_edit_singleline(&mut self.spec.replace).changed();
+            ui.end_row();
+
+            ui.label("Case");
+            ComboBox::from_id_salt("multi_rename_case")
+                .selected_text(Self::case_label(self.spec.case))
+                .show_ui(ui, |ui| {
+                    for case in [CaseTransform::Unchanged, CaseTransform::Lower, CaseTransform::Upper, CaseTransform::Title] {
+                        changed |= ui.selectable_value(&mut self.spec.case, case, Self::case_label(case)).changed();
+                    }
+                });
+            ui.end_row();
+        });
+
+        ui.small("[N] name   [E] extension   [C:start:step:width] counter   [YMD] modification date");
+        changed
+    }
+
+    fn draw_preview(ui: &mut Ui, plan: &[PlannedRename]) {
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            Grid::new("multi_rename_preview").num_columns(4).striped(true).show(ui, |ui| {
+                ui.strong("Old name");
+                ui.label("");
+                ui.strong("New name");
+                ui.label("");
+                ui.end_row();
+
+                for entry in plan {
+                    ui.label(&entry.old_name);
+                    ui.label("→");
+                    match &entry.problem {
+                        Some(problem) => {
+                            ui.colored_label(ui.visuals().error_fg_color, &entry.new_name);
+                            ui.colored_label(ui.visuals().error_fg_color, problem);
+                        }
+                        None => {
+                            ui.label(&entry.new_name);
+                            ui.label("");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn case_label(case: CaseTransform) -> &'static str {
+        match case {
+            CaseTransform::Unchanged => "Unchanged",
+            CaseTransform::Lower => "lower case",
+            CaseTransform::Upper => "UPPER CASE",
+            CaseTransform::Title => "Title Case",
+        }
+    }
+}
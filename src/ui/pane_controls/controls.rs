@@ -14,6 +14,7 @@ impl PaneControls {
         PaneControls {
             view: PaneControlsView {
                 sender,
+                follow_links: false,
             },
             receiver,
         }
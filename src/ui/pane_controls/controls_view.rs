@@ -1,18 +1,97 @@
 use tokio::sync::mpsc;
+use crate::model::conflict::LinkPolicy;
 use crate::model::pane_controls::PaneControlsEvent;
 use egui::*;
 
 pub struct PaneControlsView {
     pub sender: mpsc::Sender<PaneControlsEvent>,
+    /// Copy and move what links point at rather than the links themselves.
+    pub follow_links: bool,
 }
 
 impl PaneControlsView {
-    pub fn ui(&mut self, ui: &mut Ui) {
+    pub fn ui(&mut self, ui: &mut Ui, accepts_keys: bool) {
         ui.label("Controls");
-        let _ = ui.button("Move F6");
-        
-        if ui.input(|i| i.key_pressed(Key::F6)) {
-            self.sender.try_send(PaneControlsEvent::MoveSelected).unwrap();
+        ui.horizontal(|ui| {
+            if ui.button("Copy F5").clicked() {
+                self.send(PaneControlsEvent::CopySelected(self.link_policy()));
+            }
+            if ui.button("Move F6").clicked() {
+                self.send(PaneControlsEvent::MoveSelected(self.link_policy()));
+            }
+            ui.checkbox(&mut self.follow_links, "Follow links");
+            if ui.button("New folder F7").clicked() {
+                self.send(PaneControlsEvent::MakeDirectory);
+            }
+            if ui.button("Multi-rename Ctrl+M").clicked() {
+                self.send(PaneControlsEvent::MultiRename);
+            }
+            if ui.button("Delete F8").clicked() {
+                self.send(PaneControlsEvent::DeleteSelected);
+            }
+            ui.separator();
+            if ui.button("Trash").clicked() {
+                self.send(PaneControlsEvent::OpenTrash);
+            }
+            if ui.button("Restore").clicked() {
+                self.send(PaneControlsEvent::RestoreSelected);
+            }
+            if ui.button("Empty trash").clicked() {
+                self.send(PaneControlsEvent::EmptyTrash);
+            }
+            ui.separator();
+            if ui.button("Undo Ctrl+Z").clicked() {
+                self.send(PaneControlsEvent::Undo);
+            }
+            if ui.button("Redo Ctrl+Shift+Z").clicked() {
+                self.send(PaneControlsEvent::Redo);
+            }
+        });
+
+        if !accepts_keys {
+            return;
+        }
+
+        if ui.input(|i| i.key_pressed(Key::F5) && !i.modifiers.ctrl) {
+            self.send(PaneControlsEvent::CopySelected(self.link_policy()));
+        }
+
+        if ui.input(|i| i.key_pressed(Key::F6) && !i.modifiers.shift && !i.modifiers.ctrl) {
+            self.send(PaneControlsEvent::MoveSelected(self.link_policy()));
+        }
+
+        if ui.input(|i| i.key_pressed(Key::F7)) {
+            self.send(PaneControlsEvent::MakeDirectory);
+        }
+
+        if ui.input(|i| i.modifiers.ctrl && i.key_pressed(Key::M)) {
+            self.send(PaneControlsEvent::MultiRename);
+        }
+
+        if ui.input(|i| i.key_pressed(Key::F8)) {
+            let event = if ui.input(|i| i.modifiers.shift) {
+                PaneControlsEvent::DeleteSelectedPermanently
+            } else {
+                PaneControlsEvent::DeleteSelected
+            };
+            self.send(event);
+        }
+
+        if ui.input(|i| i.modifiers.ctrl && i.key_pressed(Key::Z)) {
+            let event = if ui.input(|i| i.modifiers.shift) {
+                PaneControlsEvent::Redo
+            } else {
+                PaneControlsEvent::Undo
+            };
+            self.send(event);
         }
     }
-}
\ No newline at end of file
+
+    fn link_policy(&self) -> LinkPolicy {
+        if self.follow_links { LinkPolicy::Dereference } else { LinkPolicy::Preserve }
+    }
+
+    fn send(&self, event: PaneControlsEvent) {
+        let _ = self.sender.try_send(event);
+    }
+}
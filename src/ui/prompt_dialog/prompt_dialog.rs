@@ -0,0 +1,54 @@
+use egui::*;
+
+/// Text typed into a prompt, plus the error from the last submit attempt.
+#[derive(Default)]
+pub struct Prompt {
+    pub text: String,
+    pub error: Option<String>,
+}
+
+pub enum PromptResult {
+    Submit,
+    Cancel,
+}
+
+/// Single-line text modal. Enter submits, Escape or clicking outside cancels.
+pub fn show(ctx: &Context, id: &str, title: &str, label: &str, prompt: &mut Prompt) -> Option<PromptResult> {
+    let mut result = None;
+
+    let modal = Modal::new(Id::new(id)).show(ctx, |ui| {
+        ui.heading(title);
+        ui.separator();
+        ui.label(label);
+
+        let response = ui.text_edit_singleline(&mut prompt.text);
+        if !response.has_focus() && !response.lost_focus() {
+            response.request_focus();
+        }
+        if response.changed() {
+            prompt.error = None;
+        }
+        if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+            result = Some(PromptResult::Submit);
+        }
+
+        if let Some(error) = &prompt.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("OK").clicked() {
+                result = Some(PromptResult::Submit);
+            }
+            if ui.button("Cancel").clicked() {
+                result = Some(PromptResult::Cancel);
+            }
+        });
+    });
+
+    if result.is_none() && modal.should_close() {
+        result = Some(PromptResult::Cancel);
+    }
+    result
+}